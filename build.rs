@@ -0,0 +1,84 @@
+use serde::Deserialize;
+use std::{env, fmt::Write, fs, path::Path};
+
+const OPCODES_PATH: &str = "ass/opcodes.json";
+
+#[derive(Deserialize)]
+struct OpcodeTable {
+    unprefixed: Vec<Opcode>,
+    cbprefixed: Vec<Opcode>,
+}
+
+#[derive(Deserialize)]
+struct Opcode {
+    name: String,
+    length: u16,
+    duration: u8,
+    duration_not_taken: Option<u8>,
+    #[serde(default)]
+    flags: OpcodeFlags,
+}
+
+#[derive(Deserialize, Default)]
+struct OpcodeFlags {
+    zero: Option<String>,
+    subtract: Option<String>,
+    halfcarry: Option<String>,
+    carry: Option<String>,
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed={OPCODES_PATH}");
+
+    let source = fs::read_to_string(OPCODES_PATH)
+        .unwrap_or_else(|err| panic!("failed to read {OPCODES_PATH}: {err}"));
+    let table: OpcodeTable = serde_json::from_str(&source)
+        .unwrap_or_else(|err| panic!("failed to parse {OPCODES_PATH}: {err}"));
+
+    let mut out = String::new();
+    write_table(&mut out, "UNPREFIXED", &table.unprefixed);
+    write_table(&mut out, "CB_PREFIXED", &table.cbprefixed);
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("opcodes.rs"), out).unwrap();
+}
+
+fn write_table(out: &mut String, name: &str, opcodes: &[Opcode]) {
+    assert!(
+        opcodes.len() == 256,
+        "{OPCODES_PATH}: {name} has {} entries, expected 256",
+        opcodes.len()
+    );
+
+    writeln!(out, "pub static {name}: [OpcodeInfo; 256] = [").unwrap();
+    for opcode in opcodes {
+        let flags = &opcode.flags;
+        writeln!(
+            out,
+            "    OpcodeInfo {{ name: {:?}, length: {}, duration: {}, \
+             duration_not_taken: {}, flags: FlagFilter::new({}, {}, {}, {}) }},",
+            opcode.name,
+            opcode.length,
+            opcode.duration,
+            opcode.duration_not_taken.unwrap_or(opcode.duration),
+            flag_rule(&opcode.name, &flags.zero),
+            flag_rule(&opcode.name, &flags.subtract),
+            flag_rule(&opcode.name, &flags.halfcarry),
+            flag_rule(&opcode.name, &flags.carry),
+        )
+        .unwrap();
+    }
+    writeln!(out, "];").unwrap();
+}
+
+fn flag_rule(name: &str, rule: &Option<String>) -> &'static str {
+    match rule.as_deref() {
+        None => "None",
+        Some("op") => "Some(FlagFilterType::Operation)",
+        Some("1") => "Some(FlagFilterType::Set)",
+        Some("0") => "Some(FlagFilterType::Reset)",
+        Some(other) => {
+            panic!("{OPCODES_PATH}: {name} has unknown flag rule {other:?}")
+        }
+    }
+}
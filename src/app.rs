@@ -33,6 +33,7 @@ pub trait Application {
     fn new_app(window: Arc<Window>) -> Self;
     fn handle_event(&mut self, event: &AppEvent) -> AppSignal;
     fn update(&mut self) -> AppSignal;
+    fn title(&self) -> String;
 }
 
 pub struct AppState<T: Application> {
@@ -49,12 +50,9 @@ impl<T: Application> AppState<T> {
         let signal = self.internal.handle_event(&event);
         self.handle_signal(signal, event_loop);
 
-        match event {
-            AppEvent::Window(WindowEvent::RedrawRequested) => {
-                let signal = self.internal.update();
-                self.handle_signal(signal, event_loop);
-            }
-            _ => {}
+        if let AppEvent::Window(WindowEvent::RedrawRequested) = event {
+            let signal = self.internal.update();
+            self.handle_signal(signal, event_loop);
         }
     }
 
@@ -68,6 +66,7 @@ impl<T: Application> AppState<T> {
             Continue => {}
             Reload => {
                 self.internal = T::new_app(self.window.clone());
+                self.window.set_title(&self.internal.title());
             }
             Quit => {
                 event_loop.exit();
@@ -82,15 +81,13 @@ pub struct App<T: Application> {
 
 impl<T: Application> ApplicationHandler for App<T> {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        let window =
-            event_loop.create_window(Window::default_attributes().with_title(
-                "TODO: figure out of a way of setting the window name.",
-            ));
+        let window = event_loop.create_window(Window::default_attributes());
 
         match window {
             Ok(window) => {
                 let window = Arc::new(window);
                 let internal = T::new_app(window.clone());
+                window.set_title(&internal.title());
 
                 self.state = Some(AppState { internal, window });
             }
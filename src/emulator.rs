@@ -1,7 +1,108 @@
+use anyhow::{bail, Result};
 use bitflags::bitflags;
+use cartridge::Cartridge;
+pub use cartridge::{Mapper, CAMERA_HEIGHT, CAMERA_WIDTH};
+use dma::{Hdma, OamDma};
+pub use header::{CgbSupport, Header};
+use interrupts::{Interrupt, Interrupts};
+use joypad::{Button, Joypad};
+use observer::{Access, BusObserver, ObserverId, Observers};
+use opcodes::OpcodeInfo;
+use ppu::{Mode, Ppu};
+use std::fmt;
+use timer::Timer;
+
+mod cartridge;
+mod dma;
+mod header;
+mod interrupts;
+mod joypad;
+pub mod observer;
+mod opcodes;
+mod ppu;
+mod timer;
+
+/// M-cycles the CPU sits still after STOP switches the CGB speed.
+const SPEED_SWITCH_CYCLES: u32 = 2050;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    /* DMG0 */ Dmg0,
+    /* DMG  */ Dmg,
+    /* MGB  */ Mgb,
+    /* SGB  */ Sgb,
+    /* CGB  */ Cgb,
+    /* AGB  */ Agb,
+}
+
+impl Model {
+    pub fn is_cgb(&self) -> bool {
+        matches!(self, Model::Cgb | Model::Agb)
+    }
+}
+
+/// I/O registers as the DMG boot ROM leaves them, the other models differ in a
+/// few of them, see `Gameboy::skip_boot_rom`.
+const POST_BOOT_IO: [(u16, u8); 32] = [
+    /* P1   */ (0xFF00, 0xCF),
+    /* SB   */ (0xFF01, 0x00),
+    /* SC   */ (0xFF02, 0x7E),
+    /* TIMA */ (0xFF05, 0x00),
+    /* TMA  */ (0xFF06, 0x00),
+    /* TAC  */ (0xFF07, 0xF8),
+    /* IF   */ (0xFF0F, 0xE1),
+    /* NR10 */ (0xFF10, 0x80),
+    /* NR11 */ (0xFF11, 0xBF),
+    /* NR12 */ (0xFF12, 0xF3),
+    /* NR13 */ (0xFF13, 0xFF),
+    /* NR14 */ (0xFF14, 0xBF),
+    /* NR21 */ (0xFF16, 0x3F),
+    /* NR22 */ (0xFF17, 0x00),
+    /* NR23 */ (0xFF18, 0xFF),
+    /* NR24 */ (0xFF19, 0xBF),
+    /* NR30 */ (0xFF1A, 0x7F),
+    /* NR31 */ (0xFF1B, 0xFF),
+    /* NR32 */ (0xFF1C, 0x9F),
+    /* NR33 */ (0xFF1D, 0xFF),
+    /* NR34 */ (0xFF1E, 0xBF),
+    /* NR41 */ (0xFF20, 0xFF),
+    /* NR42 */ (0xFF21, 0x00),
+    /* NR43 */ (0xFF22, 0x00),
+    /* NR44 */ (0xFF23, 0xBF),
+    /* NR50 */ (0xFF24, 0x77),
+    /* NR51 */ (0xFF25, 0xF3),
+    /* NR52 */ (0xFF26, 0xF1),
+    /* LCDC */ (0xFF40, 0x91),
+    /* STAT */ (0xFF41, 0x85),
+    /* BGP  */ (0xFF47, 0xFC),
+    /* IE   */ (0xFFFF, 0x00),
+];
+
+/// Bits of the I/O registers no component owns that are not connected and
+/// read 1, registers that do not exist read 0xFF. Indexed from 0xFF00, the
+/// CGB differs in a few, see `MemoryMap::io_unused_bits`.
+#[rustfmt::skip]
+const IO_UNUSED_BITS: [u8; 0x80] = [
+    /* 0xFF00 */ 0x00, 0x00, 0x7E, 0xFF, 0x00, 0x00, 0x00, 0x00,
+    /* 0xFF08 */ 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00,
+    /* 0xFF10 */ 0x80, 0x3F, 0x00, 0xFF, 0xBF, 0xFF, 0x3F, 0x00,
+    /* 0xFF18 */ 0xFF, 0xBF, 0x7F, 0xFF, 0x9F, 0xFF, 0xBF, 0xFF,
+    /* 0xFF20 */ 0xFF, 0x00, 0x00, 0xBF, 0x00, 0x00, 0x70, 0xFF,
+    /* 0xFF28 */ 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    /* 0xFF30 */ 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    /* 0xFF38 */ 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    /* 0xFF40 */ 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    /* 0xFF48 */ 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF,
+    /* 0xFF50 */ 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    /* 0xFF58 */ 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    /* 0xFF60 */ 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    /* 0xFF68 */ 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    /* 0xFF70 */ 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    /* 0xFF78 */ 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+];
 
 #[derive(Debug, Clone, Copy)]
-struct Registers {
+pub struct Registers {
     /* A */ pub accumulator: u8,
     /* F */ pub flags: Flags,
     pub b: u8,
@@ -30,9 +131,51 @@ impl Registers {
         }
     }
 
+    /// Registers as the boot ROM of `model` hands them to the cartridge at
+    /// 0x0100. The DMG and MGB boot ROMs leave H and C set unless the header
+    /// checksum is zero.
+    pub fn post_boot(model: Model, header_checksum: u8) -> Registers {
+        use Model::*;
+        let checksum_flags = if header_checksum == 0 {
+            Flags::Zero
+        } else {
+            Flags::Zero | Flags::HalfCarry | Flags::Carry
+        };
+
+        let (af, bc, de, hl) = match model {
+            Dmg0 => (0x0100, 0xFF13, 0x00C1, 0x8403),
+            Dmg => (
+                0x0100 | checksum_flags.bits() as u16,
+                0x0013,
+                0x00D8,
+                0x014D,
+            ),
+            Mgb => (
+                0xFF00 | checksum_flags.bits() as u16,
+                0x0013,
+                0x00D8,
+                0x014D,
+            ),
+            Sgb => (0x0100, 0x0014, 0x0000, 0xC060),
+            Cgb => (0x1180, 0x0000, 0xFF56, 0x000D),
+            Agb => (0x1100, 0x0100, 0xFF56, 0x000D),
+        };
+
+        let mut registers = Registers::new();
+        registers.set_reg16(Register16::AF, af);
+        registers.set_reg16(Register16::BC, bc);
+        registers.set_reg16(Register16::DE, de);
+        registers.set_reg16(Register16::HL, hl);
+        registers.stack_pointer = 0xFFFE;
+        registers.program_counter = 0x0100;
+        registers
+    }
+
     pub fn set_reg8(&mut self, reg: Register8, value: u8) {
         use Register8::*;
         match reg {
+            A => self.accumulator = value,
+            F => self.flags = Flags::from_bits_truncate(value),
             B => self.b = value,
             C => self.c = value,
             D => self.d = value,
@@ -45,6 +188,8 @@ impl Registers {
     pub fn get_reg8(&self, reg: Register8) -> u8 {
         use Register8::*;
         match reg {
+            A => self.accumulator,
+            F => self.flags.bits(),
             B => self.b,
             C => self.c,
             D => self.d,
@@ -57,6 +202,13 @@ impl Registers {
     pub fn set_reg16(&mut self, reg: Register16, value: u16) {
         use Register16::*;
         match reg {
+            AF => {
+                // the low nibble of F does not exist in hardware and always
+                // reads back as zero
+                let (a, f) = bit16_destructure(value);
+                self.accumulator = a;
+                self.flags = Flags::from_bits_truncate(f & 0xF0);
+            }
             BC => {
                 let (b, c) = bit16_destructure(value);
                 self.b = b;
@@ -73,52 +225,168 @@ impl Registers {
                 self.l = l;
             }
             StackPointer => self.stack_pointer = value,
+            ProgramCounter => self.program_counter = value,
         }
     }
 
     pub fn get_reg16(&self, reg: Register16) -> u16 {
         use Register16::*;
         match reg {
+            AF => bit16_structure(self.accumulator, self.flags.bits()),
             BC => bit16_structure(self.b, self.c),
             DE => bit16_structure(self.d, self.e),
             HL => bit16_structure(self.h, self.l),
             StackPointer => self.stack_pointer,
+            ProgramCounter => self.program_counter,
         }
     }
 }
 
-fn add16(a: u16, b: u16) -> (u16, Flagger) {
+// Arithmetic helpers return the result and every flag the computation can
+// produce, the operation's FlagFilter picks which of them reach the F register.
+
+fn add16(a: u16, b: u16) -> (u16, Flags) {
     let (result, overflow) = a.overflowing_add(b);
 
-    let mut values = Flags::Subtract;
+    let mut flags = Flags::empty();
+    if (a & 0x0FFF) + (b & 0x0FFF) > 0x0FFF {
+        flags |= Flags::HalfCarry;
+    }
     if overflow {
-        values |= Flags::Carry;
+        flags |= Flags::Carry;
     }
 
-    let flags = Flagger {
-        values,
-        mask: Flags::Carry | Flags::Subtract,
-    };
+    (result, flags)
+}
+
+/// SP plus a signed offset, half-carry and carry come from the unsigned
+/// addition of the low bytes.
+fn add16_signed(a: u16, offset: i8) -> (u16, Flags) {
+    let b = offset as i16 as u16;
+    let result = a.wrapping_add(b);
+
+    let mut flags = Flags::empty();
+    if (a & 0x000F) + (b & 0x000F) > 0x000F {
+        flags |= Flags::HalfCarry;
+    }
+    if (a & 0x00FF) + (b & 0x00FF) > 0x00FF {
+        flags |= Flags::Carry;
+    }
 
     (result, flags)
 }
 
-fn sub16(a: u16, b: u16) -> (u16, Flagger) {
-    let (result, overflow) = a.overflowing_sub(b);
+fn add8(a: u8, b: u8, carry: bool) -> (u8, Flags) {
+    let carry = carry as u8;
+    let result = a.wrapping_add(b).wrapping_add(carry);
 
-    let mut values = Flags::empty();
-    if overflow {
-        values |= Flags::Carry;
+    let mut flags = zero_flag(result);
+    if (a & 0x0F) + (b & 0x0F) + carry > 0x0F {
+        flags |= Flags::HalfCarry;
+    }
+    if (a as u16) + (b as u16) + (carry as u16) > 0xFF {
+        flags |= Flags::Carry;
     }
 
-    let flags = Flagger {
-        values,
-        mask: Flags::Carry | Flags::Subtract,
-    };
+    (result, flags)
+}
+
+fn sub8(a: u8, b: u8, carry: bool) -> (u8, Flags) {
+    let carry = carry as u8;
+    let result = a.wrapping_sub(b).wrapping_sub(carry);
+
+    let mut flags = zero_flag(result);
+    if (a & 0x0F) < (b & 0x0F) + carry {
+        flags |= Flags::HalfCarry;
+    }
+    if (a as u16) < (b as u16) + (carry as u16) {
+        flags |= Flags::Carry;
+    }
 
     (result, flags)
 }
 
+/// BCD correction of A after an ADD/ADC or SUB/SBC of two BCD numbers, N tells
+/// which of the two it was and H and C which digits carried or borrowed.
+fn daa(a: u8, flags: Flags) -> (u8, Flags) {
+    let mut correction = 0;
+    let mut carry = flags.contains(Flags::Carry);
+
+    if flags.contains(Flags::Subtract) {
+        if flags.contains(Flags::HalfCarry) {
+            correction |= 0x06;
+        }
+        if carry {
+            correction |= 0x60;
+        }
+        let result = a.wrapping_sub(correction);
+        return (result, daa_flags(result, carry));
+    }
+
+    if flags.contains(Flags::HalfCarry) || a & 0x0F > 0x09 {
+        correction |= 0x06;
+    }
+    if carry || a > 0x99 {
+        correction |= 0x60;
+        carry = true;
+    }
+    let result = a.wrapping_add(correction);
+
+    (result, daa_flags(result, carry))
+}
+
+fn daa_flags(result: u8, carry: bool) -> Flags {
+    let mut flags = zero_flag(result);
+    if carry {
+        flags |= Flags::Carry;
+    }
+
+    flags
+}
+
+fn zero_flag(result: u8) -> Flags {
+    if result == 0 {
+        Flags::Zero
+    } else {
+        Flags::empty()
+    }
+}
+
+// 0xCB rotates and shifts, take the operand and the carry flag and return the
+// result and the new carry flag.
+
+fn rlc(value: u8, _carry: bool) -> (u8, bool) {
+    (value.rotate_left(1), value & 0x80 != 0)
+}
+
+fn rrc(value: u8, _carry: bool) -> (u8, bool) {
+    (value.rotate_right(1), value & 0x01 != 0)
+}
+
+fn rl(value: u8, carry: bool) -> (u8, bool) {
+    ((value << 1) | carry as u8, value & 0x80 != 0)
+}
+
+fn rr(value: u8, carry: bool) -> (u8, bool) {
+    ((value >> 1) | ((carry as u8) << 7), value & 0x01 != 0)
+}
+
+fn sla(value: u8, _carry: bool) -> (u8, bool) {
+    (value << 1, value & 0x80 != 0)
+}
+
+fn sra(value: u8, _carry: bool) -> (u8, bool) {
+    ((value >> 1) | (value & 0x80), value & 0x01 != 0)
+}
+
+fn swap(value: u8, _carry: bool) -> (u8, bool) {
+    (value.rotate_left(4), false)
+}
+
+fn srl(value: u8, _carry: bool) -> (u8, bool) {
+    (value >> 1, value & 0x01 != 0)
+}
+
 fn bit16_destructure(value: u16) -> (u8, u8) {
     let high = ((value & 0xFF00) >> 8) as u8;
     let low = (value & 0x00FF) as u8;
@@ -130,67 +398,1267 @@ fn bit16_structure(high: u8, low: u8) -> u16 {
     ((high as u16) << 8) | (low as u16)
 }
 
-pub struct MemoryMap {}
+/// The CPU's view of the 64 KiB address space, every access is forwarded to
+/// the component that owns the address.
+pub struct MemoryMap {
+    model: Model,
+    /// 0x0000-0x7FFF and 0xA000-0xBFFF.
+    cartridge: Box<dyn Cartridge>,
+    /// Overlays 0x0000-0x00FF, and 0x0200-0x08FF on CGB, until a write to
+    /// 0xFF50 unmaps it for good.
+    boot_rom: Option<Box<[u8]>>,
+    /// The cartridge RAM outlives power off and should be saved.
+    battery: bool,
+    /// The cartridge RAM was written since the last `take_ram_written`.
+    ram_written: bool,
+    /// 0x8000-0x9FFF, 2 banks on CGB.
+    vram: Box<[u8]>,
+    /// 0xC000-0xDFFF, 8 banks on CGB, 0 is always at 0xC000.
+    wram: Box<[u8]>,
+    /* 0xFE00-0xFE9F */ oam: Box<[u8]>,
+    /// 0xFF00-0xFF7F, the I/O registers no component owns yet, they read back
+    /// what was written.
+    io: Box<[u8]>,
+    /* 0xFF80-0xFFFE */ hram: Box<[u8]>,
+    timer: Timer,
+    interrupts: Interrupts,
+    joypad: Joypad,
+    ppu: Ppu,
+    oam_dma: OamDma,
+    hdma: Hdma,
+    /// VBK, CGB only.
+    vram_bank: u8,
+    /// SVBK, CGB only, the bank at 0xD000.
+    wram_bank: u8,
+    /// KEY1 bit 7, CGB only.
+    double_speed: bool,
+    /// KEY1 bit 0, the next STOP switches speed.
+    speed_switch: bool,
+    /// Told about every CPU access.
+    observers: Observers,
+    /// Record the CPU accesses the PPU blocked in `violations`.
+    log_violations: bool,
+    violations: Vec<Violation>,
+    /// Address of the instruction the CPU is running, for the violations.
+    instruction_address: u16,
+}
 
 impl MemoryMap {
+    pub fn new(model: Model) -> Self {
+        Self {
+            model,
+            cartridge: cartridge::empty(),
+            boot_rom: None,
+            battery: false,
+            ram_written: false,
+            vram: vec![0; 0x4000].into_boxed_slice(),
+            wram: vec![0; 0x8000].into_boxed_slice(),
+            oam: vec![0; 0xA0].into_boxed_slice(),
+            io: vec![0; 0x80].into_boxed_slice(),
+            hram: vec![0; 0x7F].into_boxed_slice(),
+            timer: Timer::new(),
+            interrupts: Interrupts::new(),
+            joypad: Joypad::new(),
+            ppu: Ppu::new(),
+            oam_dma: OamDma::new(),
+            hdma: Hdma::new(),
+            vram_bank: 0,
+            wram_bank: 1,
+            double_speed: false,
+            speed_switch: false,
+            observers: Observers::new(),
+            log_violations: false,
+            violations: Vec::new(),
+            instruction_address: 0,
+        }
+    }
+
+    /// Advances every component on the bus by one CPU M-cycle, returns how
+    /// many 4 MiHz clocks that was. Components that run at the CPU's speed,
+    /// like the timer, tick once per call, the ones that run at a fixed rate
+    /// go by the returned clocks.
+    pub fn tick(&mut self) -> u32 {
+        let clocks = if self.double_speed { 2 } else { 4 };
+
+        self.cartridge.tick(clocks);
+        if let Some((source, index)) = self.oam_dma.tick() {
+            let value = self.read_bus(source);
+            self.oam_dma.bus = value;
+            self.oam[index] = value;
+        }
+        self.ppu.tick(clocks);
+        if self.ppu.hblank {
+            self.ppu.hblank = false;
+            self.hdma.start_block();
+        }
+        // 2 bytes per M-cycle in normal speed, 1 in double speed
+        for _ in 0..clocks / 2 {
+            if let Some((source, offset)) = self.hdma.next_byte() {
+                let value = self.read_bus(source);
+                self.vram[self.vram_index(offset)] = value;
+            }
+        }
+        self.interrupts.request(self.ppu.interrupt);
+        self.ppu.interrupt = Interrupt::empty();
+        self.timer.tick();
+        if self.timer.interrupt {
+            self.timer.interrupt = false;
+            self.interrupts.request(Interrupt::Timer);
+        }
+        if self.joypad.interrupt {
+            self.joypad.interrupt = false;
+            self.interrupts.request(Interrupt::Joypad);
+        }
+
+        clocks
+    }
+
+    pub fn load_rom(&mut self, rom: Vec<u8>, mapper: Mapper) -> Result<()> {
+        let header = Header::parse(&rom)?;
+        self.cartridge = cartridge::load(&header, rom, mapper)?;
+        self.battery = mapper == Mapper::Header
+            && cartridge::has_battery(header.cartridge_type);
+        self.ram_written = false;
+        Ok(())
+    }
+
+    /// A CPU read or fetch, run past the observers. A vetoed read sees open
+    /// bus.
+    pub fn observed_read(
+        &mut self,
+        access: Access,
+        address: u16,
+        cycle: u64,
+    ) -> u8 {
+        if self.log_violations {
+            self.check_violation(access, address, cycle);
+        }
+
+        let value = self.read8(address);
+        if self.observers.is_empty() {
+            return value;
+        }
+        self.observers
+            .notify(access, address, value, cycle)
+            .unwrap_or(0xFF)
+    }
+
+    /// A CPU write, run past the observers.
+    pub fn observed_write(&mut self, address: u16, value: u8, cycle: u64) {
+        if self.log_violations {
+            self.check_violation(Access::Write, address, cycle);
+        }
+
+        if self.observers.is_empty() {
+            return self.write8(address, value);
+        }
+        if let Some(value) =
+            self.observers.notify(Access::Write, address, value, cycle)
+        {
+            self.write8(address, value);
+        }
+    }
+
+    /// A CPU write, while OAM DMA runs only the I/O registers and HRAM are
+    /// reachable, and the PPU keeps VRAM and OAM to itself while it uses
+    /// them.
     pub fn write8(&mut self, address: u16, value: u8) {
-        unimplemented!()
+        let dma_blocks = self.oam_dma.active() && address < 0xFF00;
+        if dma_blocks || self.ppu_blocks(address) {
+            return;
+        }
+        self.write_bus(address, value);
+    }
+
+    /// A CPU read, while OAM DMA runs OAM reads 0xFF and the rest of the
+    /// address space below the I/O registers reads whatever the transfer put
+    /// on the bus.
+    pub fn read8(&self, address: u16) -> u8 {
+        if self.oam_dma.active() {
+            match address {
+                0xFE00..=0xFEFF => return 0xFF,
+                0x0000..=0xFDFF => return self.oam_dma.bus,
+                _ => {}
+            }
+        }
+        if self.ppu_blocks(address) {
+            return 0xFF;
+        }
+        self.read_bus(address)
+    }
+
+    fn write_bus(&mut self, address: u16, value: u8) {
+        if self.external_bus(address) {
+            self.cartridge.bus_access(address);
+        }
+
+        let index = address as usize;
+        match address {
+            0x0000..=0x7FFF => self.cartridge.write_rom(address, value),
+            0x8000..=0x9FFF => {
+                let index = self.vram_index(address - 0x8000);
+                self.vram[index] = value
+            }
+            0xA000..=0xBFFF => {
+                self.ram_written |= self.cartridge.write_ram(address, value)
+            }
+            0xC000..=0xDFFF => {
+                let index = self.wram_index(address - 0xC000);
+                self.wram[index] = value
+            }
+            0xE000..=0xFDFF => {
+                let index = self.wram_index(address - 0xE000);
+                self.wram[index] = value
+            }
+            0xFE00..=0xFE9F => self.oam[index - 0xFE00] = value,
+            0xFEA0..=0xFEFF => {}
+            0xFF00..=0xFF7F => self.write_io(address, value),
+            0xFF80..=0xFFFE => self.hram[index - 0xFF80] = value,
+            0xFFFF => self.interrupts.write(address, value),
+        }
     }
 
     pub fn write16(&mut self, address: u16, value: u16) {
-        unimplemented!()
+        let (high, low) = bit16_destructure(value);
+        self.write8(address, low);
+        self.write8(address.wrapping_add(1), high);
     }
 
-    pub fn read8(&self, address: u16) -> u8 {
-        unimplemented!()
+    fn read_bus(&self, address: u16) -> u8 {
+        if self.external_bus(address) {
+            self.cartridge.bus_access(address);
+        }
+
+        let index = address as usize;
+        match address {
+            0x0000..=0x00FF | 0x0200..=0x08FF
+                if self.boot_rom_mapped(address) =>
+            {
+                self.boot_rom.as_ref().unwrap()[index]
+            }
+            0x0000..=0x7FFF => self.cartridge.read_rom(address),
+            0x8000..=0x9FFF => self.vram[self.vram_index(address - 0x8000)],
+            0xA000..=0xBFFF => self.cartridge.read_ram(address),
+            0xC000..=0xDFFF => self.wram[self.wram_index(address - 0xC000)],
+            0xE000..=0xFDFF => self.wram[self.wram_index(address - 0xE000)],
+            0xFE00..=0xFE9F => self.oam[index - 0xFE00],
+            0xFEA0..=0xFEFF => self.unusable(address),
+            0xFF00..=0xFF7F => self.read_io(address),
+            0xFF80..=0xFFFE => self.hram[index - 0xFF80],
+            0xFFFF => self.interrupts.read(address),
+        }
     }
 
     pub fn read16(&self, address: u16) -> u16 {
-        unimplemented!()
+        let low = self.read8(address);
+        let high = self.read8(address.wrapping_add(1));
+        bit16_structure(high, low)
     }
+
+    fn write_io(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF00 => self.joypad.write(value),
+            0xFF04..=0xFF07 => self.timer.write(address, value),
+            0xFF0F => self.interrupts.write(address, value),
+            0xFF40 | 0xFF41 | 0xFF44 | 0xFF45 => self.ppu.write(address, value),
+            0xFF46 => self.oam_dma.write(value),
+            0xFF4F if self.model.is_cgb() => self.vram_bank = value & 0b1,
+            0xFF51..=0xFF55 if self.model.is_cgb() => {
+                self.hdma.write(address, value);
+                // HBlank DMA started during HBlank, or with the LCD off,
+                // copies its first block right away
+                if self.hdma.hblank()
+                    && (self.ppu.mode() == Mode::HBlank || !self.ppu.enabled())
+                {
+                    self.hdma.start_block();
+                }
+            }
+            0xFF70 if self.model.is_cgb() => {
+                self.wram_bank = (value & 0b111).max(1)
+            }
+            0xFF4F | 0xFF51..=0xFF55 | 0xFF70 => {}
+            0xFF50 => {
+                if value & 0b1 != 0 {
+                    self.boot_rom = None;
+                }
+            }
+            0xFF4D => {
+                if self.model.is_cgb() {
+                    self.speed_switch = value & 0b1 != 0
+                }
+            }
+            _ => self.io[(address - 0xFF00) as usize] = value,
+        }
+    }
+
+    fn read_io(&self, address: u16) -> u8 {
+        match address {
+            0xFF00 => self.joypad.read(),
+            0xFF04..=0xFF07 => self.timer.read(address),
+            0xFF0F => self.interrupts.read(address),
+            0xFF40 | 0xFF41 | 0xFF44 | 0xFF45 => self.ppu.read(address),
+            0xFF46 => self.oam_dma.read(),
+            0xFF4F if self.model.is_cgb() => 0b1111_1110 | self.vram_bank,
+            0xFF51..=0xFF55 if self.model.is_cgb() => self.hdma.read(address),
+            0xFF70 if self.model.is_cgb() => 0b1111_1000 | self.wram_bank,
+            0xFF4F | 0xFF51..=0xFF55 | 0xFF70 => 0xFF,
+            0xFF50 => 0xFF,
+            0xFF4D if self.model.is_cgb() => {
+                ((self.double_speed as u8) << 7)
+                    | 0b0111_1110
+                    | self.speed_switch as u8
+            }
+            0xFF4D => 0xFF,
+            _ => {
+                self.io[(address - 0xFF00) as usize]
+                    | self.io_unused_bits(address)
+            }
+        }
+    }
+
+    /// Bits of a register in `io` that read 1 whatever was written.
+    fn io_unused_bits(&self, address: u16) -> u8 {
+        if self.model.is_cgb() {
+            match address {
+                // SC has the clock speed bit
+                0xFF02 => return 0x7C,
+                // RP, the infrared port
+                0xFF56 => return 0x3C,
+                // palette indices and data
+                0xFF68 | 0xFF6A => return 0x40,
+                0xFF69 | 0xFF6B => return 0x00,
+                // OPRI, object priority mode
+                0xFF6C => return 0xFE,
+                // the undocumented registers and PCM amplitudes
+                0xFF72..=0xFF74 | 0xFF76 | 0xFF77 => return 0x00,
+                0xFF75 => return 0x8F,
+                _ => {}
+            }
+        }
+        IO_UNUSED_BITS[(address - 0xFF00) as usize]
+    }
+
+    /// Whether the PPU has the address to itself, VRAM while it draws and
+    /// OAM while it scans or draws. With the LCD off the CPU has both.
+    fn ppu_blocks(&self, address: u16) -> bool {
+        matches!(
+            (address, self.ppu.mode()),
+            (0x8000..=0x9FFF, Mode::Drawing)
+                | (0xFE00..=0xFEFF, Mode::OamScan | Mode::Drawing)
+        )
+    }
+
+    fn check_violation(&mut self, access: Access, address: u16, cycle: u64) {
+        if self.ppu_blocks(address) {
+            self.violations.push(Violation {
+                access,
+                address,
+                mode: self.ppu.mode(),
+                program_counter: self.instruction_address,
+                cycle,
+            });
+        }
+    }
+
+    /// Index into `vram` of an offset from 0x8000 in the selected bank.
+    fn vram_index(&self, offset: u16) -> usize {
+        self.vram_bank as usize * 0x2000 + (offset as usize & 0x1FFF)
+    }
+
+    /// Index into `wram` of an offset from 0xC000, 0xD000-0xDFFF is the
+    /// selected bank.
+    fn wram_index(&self, offset: u16) -> usize {
+        let offset = offset as usize & 0x1FFF;
+        match offset {
+            0x0000..=0x0FFF => offset,
+            _ => self.wram_bank as usize * 0x1000 + (offset - 0x1000),
+        }
+    }
+
+    /// Whether the address goes out on the cartridge connector, VRAM, OAM,
+    /// I/O, HRAM and the boot ROM are all inside the console.
+    fn external_bus(&self, address: u16) -> bool {
+        match address {
+            0x0100..=0x01FF => true,
+            0x0000..=0x08FF => !self.boot_rom_mapped(address),
+            0x0900..=0x7FFF | 0xA000..=0xFDFF => true,
+            _ => false,
+        }
+    }
+
+    fn boot_rom_mapped(&self, address: u16) -> bool {
+        self.boot_rom
+            .as_ref()
+            .is_some_and(|boot_rom| (address as usize) < boot_rom.len())
+    }
+
+    /// 0xFEA0-0xFEFF is not connected to anything. DMG models read 0x00, the
+    /// later CGB revisions and the AGB repeat the high nibble of the address'
+    /// low byte.
+    fn unusable(&self, address: u16) -> u8 {
+        if self.model.is_cgb() {
+            let nibble = (address as u8) & 0xF0;
+            nibble | (nibble >> 4)
+        } else {
+            0x00
+        }
+    }
+}
+
+/// The CPU hit one of the opcodes that hard lock the hardware, it only comes
+/// back with a power cycle.
+#[derive(Debug, Clone)]
+pub struct Fault {
+    pub opcode: u8,
+    /// Address the opcode was fetched from.
+    pub program_counter: u16,
+    /// Registers at the moment of the fetch, PC already moved past it.
+    pub registers: Registers,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Register16::*;
+        let registers = &self.registers;
+        write!(
+            f,
+            "illegal opcode ${:02X} at ${:04X} \
+            (AF=${:04X} BC=${:04X} DE=${:04X} HL=${:04X} SP=${:04X})",
+            self.opcode,
+            self.program_counter,
+            registers.get_reg16(AF),
+            registers.get_reg16(BC),
+            registers.get_reg16(DE),
+            registers.get_reg16(HL),
+            registers.stack_pointer,
+        )
+    }
+}
+
+impl std::error::Error for Fault {}
+
+/// A CPU access to VRAM or OAM while the PPU had it, reads see 0xFF and
+/// writes are dropped. Games that do this only work on emulators that let it
+/// through.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub access: Access,
+    pub address: u16,
+    pub mode: Mode,
+    /// Address of the instruction that made the access.
+    pub program_counter: u16,
+    pub cycle: u64,
 }
 
-struct Gameboy {
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let access = match self.access {
+            Access::Read => "read",
+            Access::Write => "write",
+            Access::Execute => "fetch",
+        };
+        let area = if self.address < 0xA000 { "VRAM" } else { "OAM" };
+        write!(
+            f,
+            "{access} of {area} ${:04X} in PPU mode {} by the instruction \
+            at ${:04X}, cycle {}",
+            self.address,
+            self.mode.bits(),
+            self.program_counter,
+            self.cycle,
+        )
+    }
+}
+
+pub struct Gameboy {
     registers: Registers,
     memory: MemoryMap,
+    /// CPU M-cycles run since power on.
+    cycles: u64,
+    /// 4 MiHz clocks run since power on, the same as 4 times `cycles` until
+    /// the CGB switches to double speed.
+    clocks: u64,
+    /// Interrupt master enable.
+    ime: bool,
+    /// Steps left until a previous EI sets IME, EI only takes effect after
+    /// the instruction that follows it.
+    ime_delay: u8,
+    halted: bool,
+    /// STOP low power mode, the system clock is off until a joypad press.
+    stopped: bool,
+    /// M-cycles left in the pause that follows a speed switch.
+    speed_switch_delay: u32,
+    /// HALT was executed with IME clear and an interrupt already pending, the
+    /// next opcode fetch does not move the program counter.
+    halt_bug: bool,
+    /// Set once the CPU locks up, nothing but the clock runs after that.
+    fault: Option<Fault>,
 }
 
 impl Gameboy {
-    pub fn new() -> Self {
+    pub fn new(model: Model) -> Self {
         Self {
             registers: Registers::new(),
-            memory: MemoryMap {},
+            memory: MemoryMap::new(model),
+            cycles: 0,
+            clocks: 0,
+            ime: false,
+            ime_delay: 0,
+            halted: false,
+            stopped: false,
+            speed_switch_delay: 0,
+            halt_bug: false,
+            fault: None,
+        }
+    }
+
+    /// Why the CPU locked up, if it did.
+    pub fn fault(&self) -> Option<&Fault> {
+        self.fault.as_ref()
+    }
+
+    pub fn press(&mut self, button: Button) {
+        self.memory.joypad.press(button);
+    }
+
+    pub fn release(&mut self, button: Button) {
+        self.memory.joypad.release(button);
+    }
+
+    /// Starts or stops recording the VRAM and OAM accesses the PPU blocks.
+    pub fn set_log_violations(&mut self, log: bool) {
+        self.memory.log_violations = log;
+    }
+
+    /// The blocked accesses since the last call, if they are being logged.
+    pub fn take_violations(&mut self) -> Vec<Violation> {
+        std::mem::take(&mut self.memory.violations)
+    }
+
+    /// Registers an observer of the CPU's memory traffic.
+    pub fn add_observer(
+        &mut self,
+        observer: Box<dyn BusObserver>,
+    ) -> ObserverId {
+        self.memory.observers.add(observer)
+    }
+
+    pub fn remove_observer(
+        &mut self,
+        id: ObserverId,
+    ) -> Option<Box<dyn BusObserver>> {
+        self.memory.observers.remove(id)
+    }
+
+    /// Tilts the cartridge, X and Y are in g from -1 to 1. Only cartridges
+    /// with an accelerometer notice.
+    pub fn set_tilt(&mut self, x: f32, y: f32) {
+        self.memory.cartridge.set_tilt(x, y);
+    }
+
+    /// Inserts the cartridge, `Mapper::detect` can pick the mapper.
+    pub fn load_rom(&mut self, rom: Vec<u8>, mapper: Mapper) -> Result<()> {
+        self.memory.load_rom(rom, mapper)
+    }
+
+    /// Maps a boot ROM over the cartridge, the CPU starts in it at 0x0000
+    /// and hands over to the cartridge by writing to 0xFF50.
+    pub fn load_boot_rom(&mut self, boot_rom: Vec<u8>) -> Result<()> {
+        let model = self.memory.model;
+        let size = if model.is_cgb() { 0x900 } else { 0x100 };
+        if boot_rom.len() != size {
+            bail!(
+                "a {model:?} boot ROM is {size} bytes, not {}",
+                boot_rom.len()
+            );
+        }
+
+        self.memory.boot_rom = Some(boot_rom.into_boxed_slice());
+        Ok(())
+    }
+
+    /// Contents of the `.sav` file, None if the cartridge has no battery.
+    pub fn save(&self) -> Option<Vec<u8>> {
+        let memory = &self.memory;
+        memory.battery.then(|| memory.cartridge.save())
+    }
+
+    /// Restores the cartridge RAM, and clock if there is one, from the
+    /// contents of a `.sav` file.
+    pub fn load_save(&mut self, save: &[u8]) -> Result<()> {
+        let memory = &mut self.memory;
+        if !memory.battery {
+            bail!("the cartridge has no battery backed RAM");
+        }
+        if !memory.cartridge.load(save) {
+            bail!("{} bytes does not match the cartridge RAM", save.len());
+        }
+        Ok(())
+    }
+
+    /// Hands the camera the images it sees, one per capture, each grayscale
+    /// and `CAMERA_WIDTH` by `CAMERA_HEIGHT`.
+    pub fn set_camera_frames(&mut self, frames: Vec<Box<[u8]>>) -> Result<()> {
+        let size = CAMERA_WIDTH * CAMERA_HEIGHT;
+        if let Some(frame) = frames.iter().find(|frame| frame.len() != size) {
+            bail!("{} bytes is not a {size} byte frame", frame.len());
+        }
+        if !self.memory.cartridge.set_camera_frames(frames) {
+            bail!("the cartridge has no camera");
+        }
+        Ok(())
+    }
+
+    /// Whether the game wrote to the cartridge RAM since the last call.
+    pub fn take_ram_written(&mut self) -> bool {
+        std::mem::take(&mut self.memory.ram_written)
+    }
+
+    /// Runs whole instructions until at least `clocks` 4 MiHz clocks have
+    /// passed. After a lock up the rest of the system keeps running.
+    pub fn run(&mut self, clocks: u64) {
+        let target = self.clocks + clocks;
+        while self.clocks < target {
+            self.step();
+        }
+    }
+
+    /// Puts the CPU and the I/O registers in the state the boot ROM leaves
+    /// them in, for running a cartridge without a boot ROM. Call it after
+    /// `load_rom`, the DMG flags depend on the header checksum.
+    pub fn skip_boot_rom(&mut self) {
+        use Model::*;
+        let model = self.memory.model;
+        let header_checksum = self.memory.read8(0x014D);
+        self.registers = Registers::post_boot(model, header_checksum);
+        // the boot ROM hands over with the mapper unlocked
+        self.memory.cartridge.skip_boot_rom();
+
+        for (address, value) in POST_BOOT_IO {
+            self.memory.write8(address, value);
+        }
+        match model {
+            Sgb => self.memory.write8(0xFF26, 0xF0),
+            Cgb | Agb => {
+                self.memory.write8(0xFF02, 0x7F);
+                self.memory.write8(0xFF41, 0x81);
+                self.memory.oam_dma.set_register(0x00);
+            }
+            _ => {}
+        }
+
+        // DIV keeps counting from power on, so it depends on how long the
+        // boot ROM ran
+        let counter = match model {
+            Dmg0 => 0x182C,
+            Dmg | Mgb => 0xABCC,
+            Sgb => 0xD8AC,
+            Cgb | Agb => 0x1EA0,
+        };
+        self.memory.timer.set_counter(counter);
+    }
+
+    /// Fetches the instruction at the program counter one byte per M-cycle
+    /// and moves the program counter past it, returns the opcode if it cannot
+    /// be decoded.
+    pub fn fetch_instruction(&mut self) -> Result<Instruction, u8> {
+        let mut bytecode = [0; 4];
+        bytecode[0] = self.fetch_byte();
+
+        if self.halt_bug {
+            self.halt_bug = false;
+            self.registers.program_counter =
+                self.registers.program_counter.wrapping_sub(1);
+        }
+
+        let mut fetched = 1;
+        if bytecode[0] == 0xCB {
+            bytecode[1] = self.fetch_byte();
+            fetched = 2;
+        }
+
+        let length = opcodes::lookup(bytecode).length as usize;
+        for byte in bytecode[fetched..length].iter_mut() {
+            *byte = self.fetch_byte();
+        }
+
+        Instruction::decode(bytecode).ok_or(bytecode[0])
+    }
+
+    /// Fetches, decodes and executes a single instruction, returns the number
+    /// of M-cycles it took. Waiting in HALT and dispatching an interrupt
+    /// count as a step of their own.
+    pub fn step(&mut self) -> u32 {
+        let start = self.cycles;
+
+        if self.stopped {
+            // the system clock is off in STOP mode, only the cartridge's own
+            // clock crystal keeps running
+            self.cycles += 1;
+            self.clocks += 4;
+            self.memory.cartridge.tick(4);
+            if self.memory.joypad.any_selected() {
+                self.stopped = false;
+            }
+            return 1;
+        }
+
+        if self.fault.is_some() {
+            // the CPU is gone but the rest of the system keeps running
+            self.tick();
+            return 1;
+        }
+
+        if self.speed_switch_delay > 0 {
+            self.speed_switch_delay -= 1;
+            self.tick();
+            return (self.cycles - start) as u32;
+        }
+
+        if self.halted {
+            self.tick();
+            if !self.memory.interrupts.pending().is_empty() {
+                self.halted = false;
+            }
+            return (self.cycles - start) as u32;
+        }
+
+        if self.memory.hdma.stalling() {
+            // the CPU waits out HDMA between instructions
+            self.tick();
+            return (self.cycles - start) as u32;
+        }
+
+        if self.ime && !self.memory.interrupts.pending().is_empty() {
+            self.dispatch_interrupt();
+            return (self.cycles - start) as u32;
+        }
+
+        let pc = self.registers.program_counter;
+        self.memory.instruction_address = pc;
+
+        let instruction = match self.fetch_instruction() {
+            Ok(instruction) => instruction,
+            Err(opcode) => {
+                self.fault = Some(Fault {
+                    opcode,
+                    program_counter: pc,
+                    registers: self.registers,
+                });
+                return (self.cycles - start) as u32;
+            }
+        };
+
+        // conditional operations never modify the flags, so checking the
+        // condition up front gives the same answer the operation will get
+        let taken = self.check_condition(instruction.operation.condition());
+        self.execute_instruction(instruction);
+
+        if self.ime_delay > 0 {
+            self.ime_delay -= 1;
+            if self.ime_delay == 0 {
+                self.ime = true;
+            }
+        }
+
+        let cycles = (self.cycles - start) as u32;
+
+        let duration = if taken {
+            instruction.info.duration
+        } else {
+            instruction.info.duration_not_taken
+        };
+        debug_assert_eq!(
+            cycles,
+            duration as u32 / 4,
+            "{instruction} at {pc:#06X} disagrees with ass/opcodes.json"
+        );
+
+        cycles
+    }
+
+    /// Executes an already fetched instruction, memory accesses and internal
+    /// delays each advance the rest of the system by one M-cycle.
+    pub fn execute_instruction(&mut self, instruction: Instruction) {
+        let computed = self.execute_operation(instruction.operation);
+
+        let filter = instruction.info.flags;
+        self.registers.flags = filter.filter(computed, self.registers.flags);
+    }
+
+    /// Pushes the program counter and jumps to the highest priority pending
+    /// interrupt, 5 M-cycles.
+    fn dispatch_interrupt(&mut self) {
+        self.ime = false;
+        self.tick();
+        self.tick();
+
+        // the HALT bug's repeated fetch never happens when the interrupt is
+        // taken instead, the handler returns to the HALT
+        let mut pc = self.registers.program_counter;
+        if self.halt_bug {
+            self.halt_bug = false;
+            pc = pc.wrapping_sub(1);
+        }
+
+        let (high, low) = bit16_destructure(pc);
+        let sp = self.registers.stack_pointer.wrapping_sub(1);
+        self.write_cycle(sp, high);
+
+        // the interrupt is picked after the high byte is pushed, a push that
+        // lands on IE can cancel it, in which case the CPU jumps to 0x0000
+        let interrupt = self.memory.interrupts.pending().highest();
+
+        let sp = sp.wrapping_sub(1);
+        self.write_cycle(sp, low);
+        self.registers.stack_pointer = sp;
+
+        self.registers.program_counter = match interrupt {
+            Some(interrupt) => {
+                self.memory.interrupts.acknowledge(interrupt);
+                interrupt.vector()
+            }
+            None => 0x0000,
+        };
+        self.tick();
+    }
+
+    /// Advances everything but the CPU by one M-cycle.
+    fn tick(&mut self) {
+        self.clocks += self.memory.tick() as u64;
+        self.cycles += 1;
+    }
+
+    fn stop(&mut self) {
+        let memory = &mut self.memory;
+
+        if memory.model.is_cgb() && memory.speed_switch {
+            memory.speed_switch = false;
+            memory.double_speed = !memory.double_speed;
+            memory.timer.write(0xFF04, 0);
+            self.speed_switch_delay = SPEED_SWITCH_CYCLES;
+            return;
+        }
+
+        // a held button keeps STOP from entering low power mode
+        if !memory.joypad.any_selected() {
+            memory.timer.write(0xFF04, 0);
+            self.stopped = true;
         }
     }
 
-    pub fn execute_operation(&mut self, op: Operation) {
+    fn read_cycle(&mut self, address: u16) -> u8 {
+        self.tick();
+        self.memory
+            .observed_read(Access::Read, address, self.cycles)
+    }
+
+    fn write_cycle(&mut self, address: u16, value: u8) {
+        self.tick();
+        self.memory.observed_write(address, value, self.cycles);
+    }
+
+    fn fetch_byte(&mut self) -> u8 {
+        let pc = self.registers.program_counter;
+        self.registers.program_counter = pc.wrapping_add(1);
+        self.tick();
+        self.memory.observed_read(Access::Execute, pc, self.cycles)
+    }
+
+    /// Runs the operation and returns the flags it computed, which flags are
+    /// kept is up to the opcode's FlagFilter.
+    fn execute_operation(&mut self, op: Operation) -> Flags {
         use Operation::*;
 
+        let mut computed = Flags::empty();
+
         match op {
             Nop => {}
-            Increment(reg) => {
+            Stop => self.stop(),
+            Halt => {
+                if !self.ime && !self.memory.interrupts.pending().is_empty() {
+                    self.halt_bug = true;
+                } else {
+                    self.halted = true;
+                }
+            }
+            DisableInterrupts => {
+                self.ime = false;
+                self.ime_delay = 0;
+            }
+            EnableInterrupts => {
+                if !self.ime && self.ime_delay == 0 {
+                    self.ime_delay = 2;
+                }
+            }
+
+            Load(dst, src) => {
+                let value = self.read_operand8(src);
+                self.write_operand8(dst, value);
+            }
+            Load16(dst, src) => {
+                let value = match src {
+                    Operand16::Immediate(value) => value,
+                    Operand16::Register(reg) => {
+                        // LD SP,HL spends a cycle on the transfer, JP HL
+                        // does not
+                        if let Register16::StackPointer = dst {
+                            self.tick();
+                        }
+                        self.registers.get_reg16(reg)
+                    }
+                };
+                self.registers.set_reg16(dst, value);
+            }
+            StoreStackPointer(address) => {
+                let (high, low) =
+                    bit16_destructure(self.registers.stack_pointer);
+                self.write_cycle(address, low);
+                self.write_cycle(address.wrapping_add(1), high);
+            }
+            LoadStackOffset(offset) => {
+                self.tick();
+                let sp = self.registers.stack_pointer;
+                let (result, flags) = add16_signed(sp, offset);
+                self.registers.set_reg16(Register16::HL, result);
+                computed = flags;
+            }
+
+            Push(reg) => {
+                self.tick();
+                let value = self.registers.get_reg16(reg);
+                self.push16(value);
+            }
+            Pop(reg) => {
+                let value = self.pop16();
+                self.registers.set_reg16(reg, value);
+                computed = self.registers.flags;
+            }
+
+            Add(operand) => {
+                let value = self.read_operand8(operand);
+                let (result, flags) =
+                    add8(self.registers.accumulator, value, false);
+                self.registers.accumulator = result;
+                computed = flags;
+            }
+            AddCarry(operand) => {
+                let value = self.read_operand8(operand);
+                let carry = self.registers.flags.contains(Flags::Carry);
+                let (result, flags) =
+                    add8(self.registers.accumulator, value, carry);
+                self.registers.accumulator = result;
+                computed = flags;
+            }
+            Sub(operand) => {
+                let value = self.read_operand8(operand);
+                let (result, flags) =
+                    sub8(self.registers.accumulator, value, false);
+                self.registers.accumulator = result;
+                computed = flags;
+            }
+            SubCarry(operand) => {
+                let value = self.read_operand8(operand);
+                let carry = self.registers.flags.contains(Flags::Carry);
+                let (result, flags) =
+                    sub8(self.registers.accumulator, value, carry);
+                self.registers.accumulator = result;
+                computed = flags;
+            }
+            And(operand) => {
+                let value = self.read_operand8(operand);
+                let result = self.registers.accumulator & value;
+                self.registers.accumulator = result;
+                computed = zero_flag(result);
+            }
+            Xor(operand) => {
+                let value = self.read_operand8(operand);
+                let result = self.registers.accumulator ^ value;
+                self.registers.accumulator = result;
+                computed = zero_flag(result);
+            }
+            Or(operand) => {
+                let value = self.read_operand8(operand);
+                let result = self.registers.accumulator | value;
+                self.registers.accumulator = result;
+                computed = zero_flag(result);
+            }
+            Compare(operand) => {
+                let value = self.read_operand8(operand);
+                let (_, flags) = sub8(self.registers.accumulator, value, false);
+                computed = flags;
+            }
+            AddHL(reg) => {
+                self.tick();
+                let hl = self.registers.get_reg16(Register16::HL);
+                let value = self.registers.get_reg16(reg);
+                let (result, flags) = add16(hl, value);
+                self.registers.set_reg16(Register16::HL, result);
+                computed = flags;
+            }
+            AddStackPointer(offset) => {
+                self.tick();
+                self.tick();
+                let sp = self.registers.stack_pointer;
+                let (result, flags) = add16_signed(sp, offset);
+                self.registers.stack_pointer = result;
+                computed = flags;
+            }
+
+            Increment(target) => {
                 use Target::*;
-                match reg {
-                    Bit8(reg) => {
-                        let read = self.registers.get_reg8(reg);
+                match target {
+                    Bit16(reg) => {
+                        self.tick();
+                        let read = self.registers.get_reg16(reg);
                         let (write, _) = read.overflowing_add(1);
-                        self.registers.set_reg8(reg, write);
+                        self.registers.set_reg16(reg, write);
+                    }
+                    _ => {
+                        let operand = target.as_operand8();
+                        let read = self.read_operand8(operand);
+                        let (write, flags) = add8(read, 1, false);
+                        self.write_operand8(operand, write);
+                        computed = flags;
                     }
+                }
+            }
+            Decrement(target) => {
+                use Target::*;
+                match target {
                     Bit16(reg) => {
+                        self.tick();
                         let read = self.registers.get_reg16(reg);
-                        let (write, _) = read.overflowing_add(1);
+                        let (write, _) = read.overflowing_sub(1);
                         self.registers.set_reg16(reg, write);
                     }
+                    _ => {
+                        let operand = target.as_operand8();
+                        let read = self.read_operand8(operand);
+                        let (write, flags) = sub8(read, 1, false);
+                        self.write_operand8(operand, write);
+                        computed = flags;
+                    }
                 }
             }
 
-            _ => {}
+            RotateLeftCircularA => {
+                computed = self.shift_target(Target::Bit8(Register8::A), rlc)
+            }
+            RotateRightCircularA => {
+                computed = self.shift_target(Target::Bit8(Register8::A), rrc)
+            }
+            RotateLeftA => {
+                computed = self.shift_target(Target::Bit8(Register8::A), rl)
+            }
+            RotateRightA => {
+                computed = self.shift_target(Target::Bit8(Register8::A), rr)
+            }
+            DecimalAdjust => {
+                let (result, flags) =
+                    daa(self.registers.accumulator, self.registers.flags);
+                self.registers.accumulator = result;
+                computed = flags;
+            }
+            Complement => {
+                self.registers.accumulator = !self.registers.accumulator;
+            }
+            SetCarry => {}
+            ComplementCarry => {
+                if !self.registers.flags.contains(Flags::Carry) {
+                    computed = Flags::Carry;
+                }
+            }
+
+            Jump(condition, address) => {
+                if self.check_condition(condition) {
+                    self.tick();
+                    self.registers.program_counter = address;
+                }
+            }
+            JumpRelative(condition, offset) => {
+                if self.check_condition(condition) {
+                    self.tick();
+                    let pc = self.registers.program_counter;
+                    self.registers.program_counter =
+                        pc.wrapping_add(offset as i16 as u16);
+                }
+            }
+            Call(condition, address) => {
+                if self.check_condition(condition) {
+                    self.tick();
+                    self.push16(self.registers.program_counter);
+                    self.registers.program_counter = address;
+                }
+            }
+            Return(condition) => {
+                // the conditional variants spend a cycle on the check
+                if condition.is_some() {
+                    self.tick();
+                }
+                if self.check_condition(condition) {
+                    self.registers.program_counter = self.pop16();
+                    self.tick();
+                }
+            }
+            ReturnInterrupt => {
+                self.registers.program_counter = self.pop16();
+                self.tick();
+                self.ime = true;
+            }
+            Restart(vector) => {
+                self.tick();
+                self.push16(self.registers.program_counter);
+                self.registers.program_counter = vector as u16;
+            }
+
+            RotateLeftCircular(target) => {
+                computed = self.shift_target(target, rlc)
+            }
+            RotateRightCircular(target) => {
+                computed = self.shift_target(target, rrc)
+            }
+            RotateLeft(target) => computed = self.shift_target(target, rl),
+            RotateRight(target) => computed = self.shift_target(target, rr),
+            ShiftLeftArithmetic(target) => {
+                computed = self.shift_target(target, sla)
+            }
+            ShiftRightArithmetic(target) => {
+                computed = self.shift_target(target, sra)
+            }
+            Swap(target) => computed = self.shift_target(target, swap),
+            ShiftRightLogical(target) => {
+                computed = self.shift_target(target, srl)
+            }
+            Bit(bit, target) => {
+                let read = self.read_operand8(target.as_operand8());
+                computed = zero_flag(read & (1 << bit));
+            }
+            Reset(bit, target) => {
+                let operand = target.as_operand8();
+                let read = self.read_operand8(operand);
+                self.write_operand8(operand, read & !(1 << bit));
+            }
+            Set(bit, target) => {
+                let operand = target.as_operand8();
+                let read = self.read_operand8(operand);
+                self.write_operand8(operand, read | (1 << bit));
+            }
+        }
+
+        computed
+    }
+
+    /// Read-modify-write shared by the rotates and shifts, returns the zero
+    /// and carry flags of the result.
+    fn shift_target(
+        &mut self,
+        target: Target,
+        shift: fn(u8, bool) -> (u8, bool),
+    ) -> Flags {
+        let operand = target.as_operand8();
+        let read = self.read_operand8(operand);
+        let carry = self.registers.flags.contains(Flags::Carry);
+
+        let (write, carry) = shift(read, carry);
+        self.write_operand8(operand, write);
+
+        let mut flags = zero_flag(write);
+        if carry {
+            flags |= Flags::Carry;
+        }
+
+        flags
+    }
+
+    fn read_operand8(&mut self, operand: Operand8) -> u8 {
+        use Operand8::*;
+        match operand {
+            Register(reg) => self.registers.get_reg8(reg),
+            Immediate(value) => value,
+            Indirect(reg) => self.read_cycle(self.registers.get_reg16(reg)),
+            IndirectIncrement => {
+                let hl = self.registers.get_reg16(Register16::HL);
+                self.registers.set_reg16(Register16::HL, hl.wrapping_add(1));
+                self.read_cycle(hl)
+            }
+            IndirectDecrement => {
+                let hl = self.registers.get_reg16(Register16::HL);
+                self.registers.set_reg16(Register16::HL, hl.wrapping_sub(1));
+                self.read_cycle(hl)
+            }
+            Address(address) => self.read_cycle(address),
+            HighAddress(offset) => self.read_cycle(0xFF00 | offset as u16),
+            HighC => self.read_cycle(0xFF00 | self.registers.c as u16),
+        }
+    }
+
+    fn write_operand8(&mut self, operand: Operand8, value: u8) {
+        use Operand8::*;
+        match operand {
+            Register(reg) => self.registers.set_reg8(reg, value),
+            Immediate(_) => unreachable!("cannot write to an immediate"),
+            Indirect(reg) => {
+                self.write_cycle(self.registers.get_reg16(reg), value)
+            }
+            IndirectIncrement => {
+                let hl = self.registers.get_reg16(Register16::HL);
+                self.registers.set_reg16(Register16::HL, hl.wrapping_add(1));
+                self.write_cycle(hl, value);
+            }
+            IndirectDecrement => {
+                let hl = self.registers.get_reg16(Register16::HL);
+                self.registers.set_reg16(Register16::HL, hl.wrapping_sub(1));
+                self.write_cycle(hl, value);
+            }
+            Address(address) => self.write_cycle(address, value),
+            HighAddress(offset) => {
+                self.write_cycle(0xFF00 | offset as u16, value)
+            }
+            HighC => self.write_cycle(0xFF00 | self.registers.c as u16, value),
+        }
+    }
+
+    /// Pushes the high byte first, one write per M-cycle. The internal delay
+    /// in front of every push is left to the caller.
+    fn push16(&mut self, value: u16) {
+        let (high, low) = bit16_destructure(value);
+
+        let sp = self.registers.stack_pointer.wrapping_sub(1);
+        self.write_cycle(sp, high);
+        let sp = sp.wrapping_sub(1);
+        self.write_cycle(sp, low);
+
+        self.registers.stack_pointer = sp;
+    }
+
+    fn pop16(&mut self) -> u16 {
+        let sp = self.registers.stack_pointer;
+        let low = self.read_cycle(sp);
+        let sp = sp.wrapping_add(1);
+        let high = self.read_cycle(sp);
+
+        self.registers.stack_pointer = sp.wrapping_add(1);
+        bit16_structure(high, low)
+    }
+
+    fn check_condition(&self, condition: Option<Condition>) -> bool {
+        use Condition::*;
+        let flags = self.registers.flags;
+        match condition {
+            None => true,
+            Some(NotZero) => !flags.contains(Flags::Zero),
+            Some(Zero) => flags.contains(Flags::Zero),
+            Some(NotCarry) => !flags.contains(Flags::Carry),
+            Some(Carry) => flags.contains(Flags::Carry),
         }
     }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Register8 {
+    A,
+    F,
     B,
     C,
     D,
@@ -201,39 +1669,379 @@ pub enum Register8 {
 
 #[derive(Debug, Clone, Copy)]
 pub enum Register16 {
+    AF,
     BC,
     DE,
     HL,
     StackPointer,
+    ProgramCounter,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Target {
     Bit8(Register8),
     Bit16(Register16),
+    Indirect(Register16),
+}
+
+impl Target {
+    fn as_operand8(self) -> Operand8 {
+        use Target::*;
+        match self {
+            Bit8(reg) => Operand8::Register(reg),
+            Indirect(reg) => Operand8::Indirect(reg),
+            Bit16(_) => unreachable!("16 bit target used as an 8 bit operand"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Operand8 {
+    /* r       */ Register(Register8),
+    /* d8      */ Immediate(u8),
+    /* (rr)    */ Indirect(Register16),
+    /* (HL+)   */ IndirectIncrement,
+    /* (HL-)   */ IndirectDecrement,
+    /* (a16)   */ Address(u16),
+    /* (a8)    */ HighAddress(u8),
+    /* (C)     */ HighC,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Operand16 {
+    /* d16 */ Immediate(u16),
+    /* rr  */ Register(Register16),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Condition {
+    /* NZ */ NotZero,
+    /* Z  */ Zero,
+    /* NC */ NotCarry,
+    /* C  */ Carry,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Operation {
-    /* NOP  */ Nop,
-    /* STOP */ Stop,
-    /* LD   */ Load,
-    Add,
-    Sub,
-    Increment(Target),
-    Decrement,
+    /* NOP         */ Nop,
+    /* STOP        */ Stop,
+    /* HALT        */ Halt,
+    /* LD          */ Load(Operand8, Operand8),
+    /* LD rr,nn    */ Load16(Register16, Operand16),
+    /* LD (a16),SP */ StoreStackPointer(u16),
+    /* LD HL,SP+e8 */ LoadStackOffset(i8),
+    /* PUSH        */ Push(Register16),
+    /* POP         */ Pop(Register16),
+    /* ADD         */ Add(Operand8),
+    /* ADC         */ AddCarry(Operand8),
+    /* SUB         */ Sub(Operand8),
+    /* SBC         */ SubCarry(Operand8),
+    /* AND         */ And(Operand8),
+    /* XOR         */ Xor(Operand8),
+    /* OR          */ Or(Operand8),
+    /* CP          */ Compare(Operand8),
+    /* ADD HL,rr   */ AddHL(Register16),
+    /* ADD SP,e8   */ AddStackPointer(i8),
+    /* INC         */ Increment(Target),
+    /* DEC         */ Decrement(Target),
+    /* RLCA        */ RotateLeftCircularA,
+    /* RRCA        */ RotateRightCircularA,
+    /* RLA         */ RotateLeftA,
+    /* RRA         */ RotateRightA,
+    /* DAA         */ DecimalAdjust,
+    /* CPL         */ Complement,
+    /* SCF         */ SetCarry,
+    /* CCF         */ ComplementCarry,
+    /* JP          */ Jump(Option<Condition>, u16),
+    /* JR          */ JumpRelative(Option<Condition>, i8),
+    /* CALL        */ Call(Option<Condition>, u16),
+    /* RET         */ Return(Option<Condition>),
+    /* RETI        */ ReturnInterrupt,
+    /* RST         */ Restart(u8),
+    /* DI          */ DisableInterrupts,
+    /* EI          */ EnableInterrupts,
+    /* RLC         */ RotateLeftCircular(Target),
+    /* RRC         */ RotateRightCircular(Target),
+    /* RL          */ RotateLeft(Target),
+    /* RR          */ RotateRight(Target),
+    /* SLA         */ ShiftLeftArithmetic(Target),
+    /* SRA         */ ShiftRightArithmetic(Target),
+    /* SWAP        */ Swap(Target),
+    /* SRL         */ ShiftRightLogical(Target),
+    /* BIT         */ Bit(u8, Target),
+    /* RES         */ Reset(u8, Target),
+    /* SET         */ Set(u8, Target),
 }
 
 impl Operation {
+    /// Branch condition of conditional jumps, calls and returns.
+    pub fn condition(&self) -> Option<Condition> {
+        use Operation::*;
+        match *self {
+            Jump(condition, _)
+            | JumpRelative(condition, _)
+            | Call(condition, _)
+            | Return(condition) => condition,
+            _ => None,
+        }
+    }
+
     pub fn parse(bytecode: [u8; 4]) -> Option<Operation> {
+        use Condition::*;
+        use Operation::*;
+
         let instruction = bytecode[0];
+        let d8 = bytecode[1];
+        let e8 = bytecode[1] as i8;
+        let d16 = bit16_structure(bytecode[2], bytecode[1]);
+        let a = Operand8::Register(Register8::A);
 
-        match instruction {
-            _ => None,
+        let op = match instruction {
+            0x00 => Nop,
+            0x10 => Stop,
+            0x76 => Halt,
+
+            // 16 bit loads
+            0x01 | 0x11 | 0x21 | 0x31 => {
+                Load16(register16(instruction >> 4), Operand16::Immediate(d16))
+            }
+            0x08 => StoreStackPointer(d16),
+            0xF8 => LoadStackOffset(e8),
+            0xF9 => Load16(
+                Register16::StackPointer,
+                Operand16::Register(Register16::HL),
+            ),
+
+            // 8 bit indirect loads
+            0x02 => Load(Operand8::Indirect(Register16::BC), a),
+            0x12 => Load(Operand8::Indirect(Register16::DE), a),
+            0x22 => Load(Operand8::IndirectIncrement, a),
+            0x32 => Load(Operand8::IndirectDecrement, a),
+            0x0A => Load(a, Operand8::Indirect(Register16::BC)),
+            0x1A => Load(a, Operand8::Indirect(Register16::DE)),
+            0x2A => Load(a, Operand8::IndirectIncrement),
+            0x3A => Load(a, Operand8::IndirectDecrement),
+            0xE0 => Load(Operand8::HighAddress(d8), a),
+            0xF0 => Load(a, Operand8::HighAddress(d8)),
+            0xE2 => Load(Operand8::HighC, a),
+            0xF2 => Load(a, Operand8::HighC),
+            0xEA => Load(Operand8::Address(d16), a),
+            0xFA => Load(a, Operand8::Address(d16)),
+
+            // 8 bit immediate loads
+            0x06 | 0x0E | 0x16 | 0x1E | 0x26 | 0x2E | 0x36 | 0x3E => {
+                Load(operand8(instruction >> 3), Operand8::Immediate(d8))
+            }
+
+            // 8 bit register loads, 0x76 is HALT and matched above
+            0x40..=0x7F => {
+                Load(operand8(instruction >> 3), operand8(instruction))
+            }
+
+            // 8 bit arithmetic
+            0x80..=0xBF => alu8(instruction >> 3, operand8(instruction)),
+            0xC6 | 0xCE | 0xD6 | 0xDE | 0xE6 | 0xEE | 0xF6 | 0xFE => {
+                alu8(instruction >> 3, Operand8::Immediate(d8))
+            }
+
+            // 16 bit arithmetic
+            0x03 | 0x13 | 0x23 | 0x33 => {
+                Increment(Target::Bit16(register16(instruction >> 4)))
+            }
+            0x0B | 0x1B | 0x2B | 0x3B => {
+                Decrement(Target::Bit16(register16(instruction >> 4)))
+            }
+            0x09 | 0x19 | 0x29 | 0x39 => AddHL(register16(instruction >> 4)),
+            0xE8 => AddStackPointer(e8),
+
+            // 8 bit increment and decrement
+            0x04 | 0x0C | 0x14 | 0x1C | 0x24 | 0x2C | 0x34 | 0x3C => {
+                Increment(target8(instruction >> 3))
+            }
+            0x05 | 0x0D | 0x15 | 0x1D | 0x25 | 0x2D | 0x35 | 0x3D => {
+                Decrement(target8(instruction >> 3))
+            }
+
+            // accumulator and flag operations
+            0x07 => RotateLeftCircularA,
+            0x0F => RotateRightCircularA,
+            0x17 => RotateLeftA,
+            0x1F => RotateRightA,
+            0x27 => DecimalAdjust,
+            0x2F => Complement,
+            0x37 => SetCarry,
+            0x3F => ComplementCarry,
+
+            // jumps
+            0x18 => JumpRelative(None, e8),
+            0x20 => JumpRelative(Some(NotZero), e8),
+            0x28 => JumpRelative(Some(Zero), e8),
+            0x30 => JumpRelative(Some(NotCarry), e8),
+            0x38 => JumpRelative(Some(Carry), e8),
+            0xC3 => Jump(None, d16),
+            0xC2 => Jump(Some(NotZero), d16),
+            0xCA => Jump(Some(Zero), d16),
+            0xD2 => Jump(Some(NotCarry), d16),
+            0xDA => Jump(Some(Carry), d16),
+            0xE9 => Load16(
+                Register16::ProgramCounter,
+                Operand16::Register(Register16::HL),
+            ),
+
+            // calls and returns
+            0xCD => Call(None, d16),
+            0xC4 => Call(Some(NotZero), d16),
+            0xCC => Call(Some(Zero), d16),
+            0xD4 => Call(Some(NotCarry), d16),
+            0xDC => Call(Some(Carry), d16),
+            0xC9 => Return(None),
+            0xC0 => Return(Some(NotZero)),
+            0xC8 => Return(Some(Zero)),
+            0xD0 => Return(Some(NotCarry)),
+            0xD8 => Return(Some(Carry)),
+            0xD9 => ReturnInterrupt,
+            0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF => {
+                Restart(instruction & 0x38)
+            }
+
+            // stack
+            0xC5 => Push(Register16::BC),
+            0xD5 => Push(Register16::DE),
+            0xE5 => Push(Register16::HL),
+            0xF5 => Push(Register16::AF),
+            0xC1 => Pop(Register16::BC),
+            0xD1 => Pop(Register16::DE),
+            0xE1 => Pop(Register16::HL),
+            0xF1 => Pop(Register16::AF),
+
+            // interrupts
+            0xF3 => DisableInterrupts,
+            0xFB => EnableInterrupts,
+
+            0xCB => Self::parse_prefixed(bytecode[1]),
+
+            // illegal opcodes
+            _ => return None,
+        };
+
+        Some(op)
+    }
+
+    /// Decodes the second byte of a 0xCB prefixed operation, every one of the
+    /// 256 values is a valid operation.
+    fn parse_prefixed(instruction: u8) -> Operation {
+        use Operation::*;
+
+        let target = target8(instruction);
+        let bit = (instruction >> 3) & 0b111;
+
+        match instruction >> 6 {
+            0 => match bit {
+                0 => RotateLeftCircular(target),
+                1 => RotateRightCircular(target),
+                2 => RotateLeft(target),
+                3 => RotateRight(target),
+                4 => ShiftLeftArithmetic(target),
+                5 => ShiftRightArithmetic(target),
+                6 => Swap(target),
+                _ => ShiftRightLogical(target),
+            },
+            1 => Bit(bit, target),
+            2 => Reset(bit, target),
+            _ => Set(bit, target),
         }
     }
 }
 
+/// Decodes the 3 bit register index used throughout the opcode table,
+/// B, C, D, E, H, L, (HL), A.
+fn operand8(index: u8) -> Operand8 {
+    match index & 0b111 {
+        0 => Operand8::Register(Register8::B),
+        1 => Operand8::Register(Register8::C),
+        2 => Operand8::Register(Register8::D),
+        3 => Operand8::Register(Register8::E),
+        4 => Operand8::Register(Register8::H),
+        5 => Operand8::Register(Register8::L),
+        6 => Operand8::Indirect(Register16::HL),
+        _ => Operand8::Register(Register8::A),
+    }
+}
+
+fn target8(index: u8) -> Target {
+    match operand8(index) {
+        Operand8::Register(reg) => Target::Bit8(reg),
+        _ => Target::Indirect(Register16::HL),
+    }
+}
+
+/// Decodes the 2 bit register pair index, BC, DE, HL, SP.
+fn register16(index: u8) -> Register16 {
+    match index & 0b11 {
+        0 => Register16::BC,
+        1 => Register16::DE,
+        2 => Register16::HL,
+        _ => Register16::StackPointer,
+    }
+}
+
+/// Decodes the 3 bit arithmetic index, ADD, ADC, SUB, SBC, AND, XOR, OR, CP.
+fn alu8(index: u8, operand: Operand8) -> Operation {
+    use Operation::*;
+    match index & 0b111 {
+        0 => Add(operand),
+        1 => AddCarry(operand),
+        2 => Sub(operand),
+        3 => SubCarry(operand),
+        4 => And(operand),
+        5 => Xor(operand),
+        6 => Or(operand),
+        _ => Compare(operand),
+    }
+}
+
+/// A decoded operation together with its bytes and opcode metadata.
+#[derive(Debug, Clone, Copy)]
+pub struct Instruction {
+    pub bytecode: [u8; 4],
+    pub operation: Operation,
+    pub info: &'static OpcodeInfo,
+}
+
+impl Instruction {
+    pub fn decode(bytecode: [u8; 4]) -> Option<Instruction> {
+        let operation = Operation::parse(bytecode)?;
+        let info = opcodes::lookup(bytecode);
+
+        Some(Self {
+            bytecode,
+            operation,
+            info,
+        })
+    }
+}
+
+impl fmt::Display for Instruction {
+    /// Disassembles the instruction by filling the immediates into the
+    /// opcode's name.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let d8 = self.bytecode[1];
+        let d16 = bit16_structure(self.bytecode[2], self.bytecode[1]);
+
+        let text = self
+            .info
+            .name
+            .replace("d16", &format!("${d16:04X}"))
+            .replace("a16", &format!("${d16:04X}"))
+            .replace("d8", &format!("${d8:02X}"))
+            .replace("a8", &format!("$FF{d8:02X}"))
+            .replace("+r8", &format!("{:+}", d8 as i8))
+            .replace("r8", &format!("{:+}", d8 as i8));
+
+        f.write_str(&text)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum FlagFilterType {
     Operation,
@@ -250,8 +2058,49 @@ pub struct FlagFilter {
 }
 
 impl FlagFilter {
+    pub const fn new(
+        zero: Option<FlagFilterType>,
+        subtract: Option<FlagFilterType>,
+        halfcarry: Option<FlagFilterType>,
+        carry: Option<FlagFilterType>,
+    ) -> Self {
+        Self {
+            zero,
+            subtract,
+            halfcarry,
+            carry,
+        }
+    }
+
+    /// Resolves the filter against the flags an operation computed, flags
+    /// without a rule are left out of the mask and keep their old value.
+    pub fn flagger(&self, computed: Flags) -> Flagger {
+        let rules = [
+            (self.zero, Flags::Zero),
+            (self.subtract, Flags::Subtract),
+            (self.halfcarry, Flags::HalfCarry),
+            (self.carry, Flags::Carry),
+        ];
+
+        let mut flagger = Flagger::new();
+        for (rule, flag) in rules {
+            use FlagFilterType::*;
+            match rule {
+                None => continue,
+                Some(Operation) => flagger.values |= computed & flag,
+                Some(Set) => flagger.values |= flag,
+                Some(Reset) => {}
+            }
+            flagger.mask |= flag;
+        }
+
+        flagger
+    }
+
+    /// Combines the flags computed by an operation, `src`, with the current
+    /// flags, `dst`.
     pub fn filter(&self, src: Flags, dst: Flags) -> Flags {
-        unimplemented!()
+        self.flagger(src).apply(dst)
     }
 }
 
@@ -277,4 +2126,245 @@ impl Flagger {
             mask: Flags::empty(),
         }
     }
+
+    /// Overwrites the masked flags in `flags` with the computed values.
+    pub fn apply(&self, flags: Flags) -> Flags {
+        (flags - self.mask) | (self.values & self.mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Opcodes that lock up the CPU.
+    const ILLEGAL: [u8; 11] = [
+        0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD,
+    ];
+
+    /// Where the instruction under test is run from, in WRAM.
+    const CODE: u16 = 0xC000;
+    /// What the immediates and the register pairs point at, also in WRAM.
+    const DATA: u16 = 0xC800;
+
+    /// Every legal opcode with its immediates pointing at `DATA`, relative
+    /// jumps land on the next instruction.
+    fn legal_opcodes() -> impl Iterator<Item = [u8; 4]> {
+        let [high, low] = DATA.to_be_bytes();
+        let unprefixed = (0x00..=0xFF)
+            .filter(|opcode| *opcode != 0xCB && !ILLEGAL.contains(opcode))
+            .map(move |opcode| [opcode, low, high, 0]);
+        let prefixed = (0x00..=0xFF).map(|opcode| [0xCB, opcode, 0, 0]);
+        unprefixed.chain(prefixed)
+    }
+
+    /// A Game Boy about to run the instruction from `CODE`, with interrupts
+    /// disabled and every register pair pointing at `DATA`.
+    fn gameboy(bytecode: [u8; 4], flags: Flags) -> Gameboy {
+        let mut gameboy = Gameboy::new(Model::Dmg);
+        for (address, byte) in (CODE..).zip(bytecode) {
+            gameboy.memory.write8(address, byte);
+        }
+
+        let [high, low] = DATA.to_be_bytes();
+        let registers = &mut gameboy.registers;
+        (registers.b, registers.c) = (high, low);
+        (registers.d, registers.e) = (high, low);
+        (registers.h, registers.l) = (high, low);
+        registers.flags = flags;
+        registers.program_counter = CODE;
+        registers.stack_pointer = DATA + 0x100;
+        gameboy
+    }
+
+    fn branches(operation: Operation) -> bool {
+        use Operation::*;
+        matches!(
+            operation,
+            Load16(Register16::ProgramCounter, _)
+                | Jump(..)
+                | JumpRelative(..)
+                | Call(..)
+                | Return(_)
+                | ReturnInterrupt
+                | Restart(_)
+        )
+    }
+
+    /// Two digit decimal as packed BCD.
+    fn bcd(value: u32) -> u8 {
+        ((value / 10) << 4 | (value % 10)) as u8
+    }
+
+    /// Adding or subtracting two BCD bytes and then running DAA gives the
+    /// BCD result, with carry as the decimal carry or borrow.
+    #[test]
+    fn daa_adjusts_bcd_arithmetic() {
+        // ADD A,B, ADC A,B, SUB B and SBC A,B, each followed by DAA
+        for opcode in [0x80, 0x88, 0x90, 0x98] {
+            let subtract = opcode >= 0x90;
+            let mut gameboy = gameboy([opcode, 0x27, 0, 0], Flags::empty());
+
+            for (a, b, carry) in (0..100)
+                .flat_map(|a| (0..100).map(move |b| (a, b)))
+                .flat_map(|(a, b)| [(a, b, false), (a, b, true)])
+            {
+                let registers = &mut gameboy.registers;
+                registers.program_counter = CODE;
+                registers.accumulator = bcd(a);
+                registers.b = bcd(b);
+                registers.flags =
+                    if carry { Flags::Carry } else { Flags::empty() };
+                gameboy.step();
+                gameboy.step();
+
+                let carry_in = (carry && opcode & 0x08 != 0) as i32;
+                let result = if subtract {
+                    a as i32 - b as i32 - carry_in
+                } else {
+                    a as i32 + b as i32 + carry_in
+                };
+                let expected = bcd(result.rem_euclid(100) as u32);
+                let flags = gameboy.registers.flags;
+                let name = opcodes::lookup([opcode, 0, 0, 0]).name;
+                let case = format!("{name} with {a}, {b} and carry {carry}");
+
+                assert_eq!(gameboy.registers.accumulator, expected, "{case}");
+                assert_eq!(
+                    flags.contains(Flags::Zero),
+                    expected == 0,
+                    "{case}"
+                );
+                assert_eq!(flags.contains(Flags::Subtract), subtract, "{case}");
+                assert!(!flags.contains(Flags::HalfCarry), "{case}");
+                assert_eq!(
+                    flags.contains(Flags::Carry),
+                    !(0..100).contains(&result),
+                    "{case}"
+                );
+            }
+        }
+    }
+
+    /// DAA on bytes that are not BCD, the results real hardware gives.
+    #[test]
+    fn daa_adjusts_invalid_bcd() {
+        let cases = [
+            (0x0A, Flags::empty(), 0x10, Flags::empty()),
+            (0x9A, Flags::empty(), 0x00, Flags::Zero | Flags::Carry),
+            (0xFF, Flags::empty(), 0x65, Flags::Carry),
+            (0x00, Flags::HalfCarry, 0x06, Flags::empty()),
+            (
+                0x00,
+                Flags::Subtract | Flags::HalfCarry,
+                0xFA,
+                Flags::empty(),
+            ),
+            (
+                0x00,
+                Flags::Subtract | Flags::HalfCarry | Flags::Carry,
+                0x9A,
+                Flags::Carry,
+            ),
+        ];
+        for (a, flags, result, computed) in cases {
+            let (actual, actual_flags) = daa(a, flags);
+            assert_eq!(
+                (actual, actual_flags.bits()),
+                (result, computed.bits()),
+                "DAA of ${a:02X} with {flags:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn illegal_opcodes_do_not_decode() {
+        for opcode in ILLEGAL {
+            assert!(Instruction::decode([opcode, 0, 0, 0]).is_none());
+        }
+    }
+
+    /// The decoder reads every immediate byte the table counts and nothing
+    /// past them.
+    #[test]
+    fn decode_matches_table_lengths() {
+        for bytecode in legal_opcodes() {
+            let instruction = Instruction::decode(bytecode)
+                .unwrap_or_else(|| panic!("{bytecode:02X?} does not decode"));
+            let length = instruction.info.length as usize;
+            let operation = format!("{:?}", instruction.operation);
+
+            let mut padded = bytecode;
+            padded[length..].fill(0xA5);
+            let parsed = Operation::parse(padded).unwrap();
+            assert_eq!(
+                format!("{parsed:?}"),
+                operation,
+                "{instruction} reads past its {length} bytes"
+            );
+
+            // STOP's second byte is padding, whatever it is
+            let prefix = match bytecode[0] {
+                0xCB => 2,
+                0x10 => length,
+                _ => 1,
+            };
+            for index in prefix..length {
+                let mut changed = bytecode;
+                changed[index] ^= 0x5A;
+                let parsed = Operation::parse(changed).unwrap();
+                assert_ne!(
+                    format!("{parsed:?}"),
+                    operation,
+                    "{instruction} ignores byte {index} of its {length}"
+                );
+            }
+        }
+    }
+
+    /// Each opcode runs for the M-cycles the table gives, with its condition
+    /// both met and not met, and moves PC past its bytes unless it branches.
+    #[test]
+    fn step_matches_table_timings() {
+        for bytecode in legal_opcodes() {
+            let instruction = Instruction::decode(bytecode).unwrap();
+            let info = instruction.info;
+            let condition = instruction.operation.condition();
+            let mut outcomes = Vec::new();
+
+            for flags in [Flags::all(), Flags::empty()] {
+                let mut gameboy = gameboy(bytecode, flags);
+                let taken = gameboy.check_condition(condition);
+                outcomes.push(taken);
+
+                let cycles = gameboy.step();
+                let duration = if taken {
+                    info.duration
+                } else {
+                    info.duration_not_taken
+                };
+                assert_eq!(
+                    cycles,
+                    duration as u32 / 4,
+                    "{instruction} with {flags:?}, taken: {taken}"
+                );
+                assert!(gameboy.fault().is_none(), "{instruction} faulted");
+
+                if !taken || !branches(instruction.operation) {
+                    assert_eq!(
+                        gameboy.registers.program_counter,
+                        CODE + info.length,
+                        "{instruction} with {flags:?}, taken: {taken}"
+                    );
+                }
+            }
+
+            if condition.is_some() {
+                assert!(
+                    outcomes.contains(&true) && outcomes.contains(&false),
+                    "{instruction} was not run both ways"
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,267 @@
+use super::header::Header;
+use anyhow::{bail, Result};
+use camera::Camera;
+use huc1::HuC1;
+use huc3::HuC3;
+use mbc1::Mbc1;
+use mbc2::Mbc2;
+use mbc3::Mbc3;
+use mbc5::Mbc5;
+use mbc6::Mbc6;
+use mbc7::Mbc7;
+use mmm01::Mmm01;
+use multicart::Multicart;
+use rom::Rom;
+use sachen::{Sachen, SachenMmc};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tama5::Tama5;
+use wisdom_tree::WisdomTree;
+
+pub use camera::{CAMERA_HEIGHT, CAMERA_WIDTH};
+
+mod camera;
+mod huc1;
+mod huc3;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod mbc6;
+mod mbc7;
+mod mmm01;
+mod multicart;
+mod rom;
+mod rtc;
+mod sachen;
+mod tama5;
+mod wisdom_tree;
+
+const ROM_BANK_SIZE: usize = 0x4000;
+const RAM_BANK_SIZE: usize = 0x2000;
+
+/// Whatever sits behind the cartridge slot, the bus forwards 0x0000-0x7FFF to
+/// the `rom` methods and 0xA000-0xBFFF to the `ram` ones. Writes to the ROM
+/// area are how the mapper registers get set.
+pub trait Cartridge {
+    fn read_rom(&self, address: u16) -> u8;
+    fn write_rom(&mut self, address: u16, value: u8);
+    fn read_ram(&self, address: u16) -> u8;
+    /// Returns whether the write went to memory that `save` covers, writes
+    /// to disabled RAM and mapper registers leave the save file alone.
+    fn write_ram(&mut self, address: u16, value: u8) -> bool;
+
+    /// Called once per CPU M-cycle with the 4 MiHz clocks it took, for
+    /// cartridges with hardware of their own like a clock.
+    fn tick(&mut self, _clocks: u32) {}
+
+    /// Tilt of the cartridge in g along the X and Y axes, -1 to 1, for
+    /// cartridges with an accelerometer.
+    fn set_tilt(&mut self, _x: f32, _y: f32) {}
+
+    /// Called for every access on the external bus, cartridge ROM and RAM
+    /// and WRAM, for cartridges that watch the address lines.
+    fn bus_access(&self, _address: u16) {}
+
+    /// Puts the cartridge in the state the boot ROM leaves it in, for
+    /// cartridges that the boot ROM has to unlock.
+    fn skip_boot_rom(&mut self) {}
+
+    /// Grayscale `CAMERA_WIDTH` by `CAMERA_HEIGHT` images for cartridges
+    /// with a camera to see, returns false if there is no camera.
+    fn set_camera_frames(&mut self, _frames: Vec<Box<[u8]>>) -> bool {
+        false
+    }
+
+    /// Battery backed state in the `.sav` layout, the RAM followed by a
+    /// footer for cartridges with a clock.
+    fn save(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Restores what `save` returned, returns false if it does not fit the
+    /// cartridge.
+    fn load(&mut self, _save: &[u8]) -> bool {
+        false
+    }
+}
+
+/// Which mapper to emulate. Most cartridges say in their header, the
+/// unlicensed ones lie and have to be recognised from the ROM contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mapper {
+    /// Whatever the header cartridge type says.
+    Header,
+    WisdomTree,
+    SachenMmc1,
+    SachenMmc2,
+    /// A pirate "N in 1" multicart.
+    Multicart,
+}
+
+impl Mapper {
+    /// Guesses whether the cartridge is one of the unlicensed ones.
+    pub fn detect(header: &Header, rom: &[u8]) -> Mapper {
+        let kind = header.cartridge_type;
+        if let Some(sachen) = sachen::detect(rom) {
+            match sachen {
+                Sachen::Mmc1 => Mapper::SachenMmc1,
+                Sachen::Mmc2 => Mapper::SachenMmc2,
+            }
+        } else if wisdom_tree::detect(rom, kind) {
+            Mapper::WisdomTree
+        } else if multicart::detect(rom, kind, header.rom_size) {
+            Mapper::Multicart
+        } else {
+            Mapper::Header
+        }
+    }
+}
+
+impl fmt::Display for Mapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Mapper::Header => "header",
+            Mapper::WisdomTree => "wisdom-tree",
+            Mapper::SachenMmc1 => "sachen-mmc1",
+            Mapper::SachenMmc2 => "sachen-mmc2",
+            Mapper::Multicart => "multicart",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Mapper {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Ok(match name {
+            "header" => Mapper::Header,
+            "wisdom-tree" => Mapper::WisdomTree,
+            "sachen-mmc1" => Mapper::SachenMmc1,
+            "sachen-mmc2" => Mapper::SachenMmc2,
+            "multicart" => Mapper::Multicart,
+            _ => bail!(
+                "unknown mapper {name:?}, expected header, wisdom-tree, \
+                 sachen-mmc1, sachen-mmc2 or multicart"
+            ),
+        })
+    }
+}
+
+/// Builds the mapper, for `Mapper::Header` from the header cartridge type.
+pub fn load(
+    header: &Header,
+    rom: Vec<u8>,
+    mapper: Mapper,
+) -> Result<Box<dyn Cartridge>> {
+    let kind = header.cartridge_type;
+    let ram = vec![0; header.ram_size].into_boxed_slice();
+    let rom = rom.into_boxed_slice();
+
+    match mapper {
+        Mapper::Header => {}
+        Mapper::WisdomTree => return Ok(Box::new(WisdomTree::new(rom))),
+        Mapper::SachenMmc1 => {
+            return Ok(Box::new(SachenMmc::new(rom, Sachen::Mmc1)))
+        }
+        Mapper::SachenMmc2 => {
+            return Ok(Box::new(SachenMmc::new(rom, Sachen::Mmc2)))
+        }
+        Mapper::Multicart => return Ok(Box::new(Multicart::new(rom, ram))),
+    }
+
+    let cartridge: Box<dyn Cartridge> = match kind {
+        0x00 | 0x08 | 0x09 => Box::new(Rom::new(rom, ram)),
+        0x01..=0x03 => Box::new(Mbc1::new(rom, ram)),
+        0x05 | 0x06 => Box::new(Mbc2::new(rom)),
+        0x0B..=0x0D => Box::new(Mmm01::new(rom, ram)),
+        0x0F | 0x10 => Box::new(Mbc3::new(rom, ram, true)),
+        0x11..=0x13 => Box::new(Mbc3::new(rom, ram, false)),
+        0x19..=0x1E => Box::new(Mbc5::new(rom, ram)),
+        0x20 => Box::new(Mbc6::new(rom, ram)),
+        0x22 => Box::new(Mbc7::new(rom)),
+        0xFC => Box::new(Camera::new(rom)),
+        0xFD => Box::new(Tama5::new(rom)),
+        0xFE => Box::new(HuC3::new(rom, ram)),
+        0xFF => Box::new(HuC1::new(rom, ram)),
+        _ => bail!("unsupported cartridge type {kind:#04X}"),
+    };
+
+    Ok(cartridge)
+}
+
+/// Whether the cartridge type keeps its RAM powered with a battery.
+pub fn has_battery(kind: u8) -> bool {
+    matches!(
+        kind,
+        0x03 | 0x06
+            | 0x09
+            | 0x0D
+            | 0x0F
+            | 0x10
+            | 0x13
+            | 0x1B
+            | 0x1E
+            | 0x20
+            | 0x22
+            | 0xFC
+            | 0xFD
+            | 0xFE
+            | 0xFF
+    )
+}
+
+/// An empty cartridge slot, the data lines float high.
+pub fn empty() -> Box<dyn Cartridge> {
+    let rom = vec![0xFF; 2 * ROM_BANK_SIZE].into_boxed_slice();
+    Box::new(Rom::new(rom, Box::new([])))
+}
+
+/// Seconds since the UNIX epoch, stored in saves so clocks can catch up on
+/// the time the emulator was closed.
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+// Banks past the end of the chip wrap around, the mappers drive more address
+// lines than small ROMs and RAMs have.
+
+fn read_rom_bank(rom: &[u8], bank: usize, address: u16) -> u8 {
+    let offset = bank * ROM_BANK_SIZE + (address as usize & 0x3FFF);
+    rom[offset % rom.len()]
+}
+
+fn read_ram_bank(ram: &[u8], bank: usize, address: u16) -> u8 {
+    if ram.is_empty() {
+        return 0xFF;
+    }
+    let offset = bank * RAM_BANK_SIZE + (address as usize & 0x1FFF);
+    ram[offset % ram.len()]
+}
+
+/// Copies a save into RAM if it is exactly the RAM's size.
+fn load_ram(ram: &mut [u8], save: &[u8]) -> bool {
+    if save.len() != ram.len() {
+        return false;
+    }
+    ram.copy_from_slice(save);
+    true
+}
+
+fn write_ram_bank(
+    ram: &mut [u8],
+    bank: usize,
+    address: u16,
+    value: u8,
+) -> bool {
+    if ram.is_empty() {
+        return false;
+    }
+    let offset = bank * RAM_BANK_SIZE + (address as usize & 0x1FFF);
+    ram[offset % ram.len()] = value;
+    true
+}
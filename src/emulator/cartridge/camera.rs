@@ -0,0 +1,236 @@
+use super::{
+    load_ram, read_ram_bank, read_rom_bank, write_ram_bank, Cartridge,
+};
+
+/// Size of the image the camera captures, the frames handed to it have to
+/// be grayscale at this size.
+pub const CAMERA_WIDTH: usize = 128;
+pub const CAMERA_HEIGHT: usize = 112;
+
+const RAM_SIZE: usize = 0x2_0000;
+/// Where the captured image lands in RAM bank 0, as 16x14 tiles.
+const IMAGE_OFFSET: usize = 0x0100;
+const REGISTERS: usize = 0x36;
+const MATRIX: usize = 0x06;
+/// Exposure that passes the light through unchanged.
+const EXPOSURE_UNITY: u32 = 0x0800;
+/// Edge enhancement ratios selected by bits 4-6 of register 4, in eighths.
+const EDGE_RATIOS: [i32; 8] = [4, 6, 8, 10, 16, 24, 32, 40];
+/// Sensor output when no image has been loaded.
+const GREY: u8 = 0x80;
+
+/// The Pocket Camera, 1 MiB of ROM, 128 KiB of RAM and a 128x128 image
+/// sensor, of which 128x112 is used.
+///
+/// With bit 4 of the RAM bank register set, 0xA000-0xBFFF shows the sensor
+/// registers instead of RAM. Writing 1 to bit 0 of register 0 starts a
+/// capture, the bit reads 1 until the processed and dithered image has been
+/// written to RAM. The sensor sees the frames set by `set_camera_frames`, one
+/// frame per capture, looping.
+pub struct Camera {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    ram_enable: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+    /// 0xA000-0xBFFF shows the registers.
+    registers_mapped: bool,
+    /// A000-A005 are the sensor settings, A006-A035 the 4x4 dithering
+    /// matrix of three thresholds per pixel.
+    registers: [u8; REGISTERS],
+    /// 4 MiHz clocks left until the capture in progress finishes.
+    capture: Option<u32>,
+    frames: Vec<Box<[u8]>>,
+    frame: usize,
+}
+
+impl Camera {
+    pub fn new(rom: Box<[u8]>) -> Self {
+        Self {
+            rom,
+            ram: vec![0; RAM_SIZE].into_boxed_slice(),
+            ram_enable: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            registers_mapped: false,
+            registers: [0; REGISTERS],
+            capture: None,
+            frames: Vec::new(),
+            frame: 0,
+        }
+    }
+
+    fn exposure(&self) -> u32 {
+        u16::from_be_bytes([self.registers[2], self.registers[3]]) as u32
+    }
+
+    /// How long the sensor takes to read out, in 4 MiHz clocks.
+    fn capture_clocks(&self) -> u32 {
+        let exclusive_edge = self.registers[1] & 0b1000_0000 != 0;
+        let m_cycles =
+            32446 + if exclusive_edge { 0 } else { 512 } + 16 * self.exposure();
+        4 * m_cycles
+    }
+
+    /// The light the sensor saw, scaled by the exposure time and run
+    /// through the edge enhancement and inversion the registers select.
+    fn sense(&self) -> Vec<i32> {
+        let frame = self.frames.get(self.frame);
+        let exposure = self.exposure();
+        let light: Vec<i32> = (0..CAMERA_WIDTH * CAMERA_HEIGHT)
+            .map(|index| {
+                let pixel = frame.map_or(GREY, |frame| frame[index]) as u32;
+                (pixel * exposure / EXPOSURE_UNITY).min(0xFF) as i32
+            })
+            .collect();
+
+        let ratio = EDGE_RATIOS[(self.registers[4] >> 4) as usize & 0b111];
+        let invert = self.registers[4] & 0b0000_1000 != 0;
+        let at = |x: usize, y: usize| light[y * CAMERA_WIDTH + x];
+
+        let mut output = Vec::with_capacity(light.len());
+        for y in 0..CAMERA_HEIGHT {
+            for x in 0..CAMERA_WIDTH {
+                let center = at(x, y);
+                let horizontal = 2 * center
+                    - at(x.saturating_sub(1), y)
+                    - at((x + 1).min(CAMERA_WIDTH - 1), y);
+                let vertical = 2 * center
+                    - at(x, y.saturating_sub(1))
+                    - at(x, (y + 1).min(CAMERA_HEIGHT - 1));
+                let edge = match (self.registers[1] >> 5) & 0b11 {
+                    0b00 => 0,
+                    0b01 => horizontal,
+                    0b10 => vertical,
+                    _ => horizontal + vertical,
+                };
+
+                let value = (center + edge * ratio / 8).clamp(0, 0xFF);
+                output.push(if invert { 0xFF - value } else { value });
+            }
+        }
+        output
+    }
+
+    /// Dithers what the sensor saw through the matrix and writes it to RAM
+    /// as tiles, darker than the first threshold is color 3 and brighter
+    /// than the last is color 0.
+    fn finish_capture(&mut self) {
+        let sensed = self.sense();
+        let matrix = &self.registers[MATRIX..];
+
+        for y in 0..CAMERA_HEIGHT {
+            for x in 0..CAMERA_WIDTH {
+                let value = sensed[y * CAMERA_WIDTH + x];
+                let cell = 3 * ((y % 4) * 4 + x % 4);
+                let thresholds = &matrix[cell..cell + 3];
+                let color = match thresholds
+                    .iter()
+                    .position(|&threshold| value < threshold as i32)
+                {
+                    Some(level) => 3 - level as u8,
+                    None => 0,
+                };
+
+                let tile = (y / 8) * (CAMERA_WIDTH / 8) + x / 8;
+                let row = IMAGE_OFFSET + tile * 16 + (y % 8) * 2;
+                let bit = 0x80 >> (x % 8);
+                for (plane, mask) in [(0, 0b01), (1, 0b10)] {
+                    let byte = &mut self.ram[row + plane];
+                    if color & mask != 0 {
+                        *byte |= bit;
+                    } else {
+                        *byte &= !bit;
+                    }
+                }
+            }
+        }
+
+        if !self.frames.is_empty() {
+            self.frame = (self.frame + 1) % self.frames.len();
+        }
+        self.registers[0] &= !0b1;
+    }
+}
+
+impl Cartridge for Camera {
+    fn read_rom(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => 0,
+            _ => self.rom_bank as usize,
+        };
+        read_rom_bank(&self.rom, bank, address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enable = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x3F,
+            0x4000..=0x5FFF => {
+                self.registers_mapped = value & 0b1_0000 != 0;
+                self.ram_bank = value & 0x0F;
+            }
+            _ => {}
+        }
+    }
+
+    /// RAM can be read without enabling it, only writes need it. Of the
+    /// registers only the capture bit can be read back.
+    fn read_ram(&self, address: u16) -> u8 {
+        match self.registers_mapped {
+            true if address & 0x7F == 0 => self.registers[0] & 0b1,
+            true => 0x00,
+            false => read_ram_bank(&self.ram, self.ram_bank as usize, address),
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        if !self.ram_enable {
+            return false;
+        }
+        if !self.registers_mapped {
+            let bank = self.ram_bank as usize;
+            return write_ram_bank(&mut self.ram, bank, address, value);
+        }
+
+        let index = (address & 0x7F) as usize;
+        match index {
+            0 => {
+                self.registers[0] = value & 0b111;
+                if value & 0b1 != 0 && self.capture.is_none() {
+                    self.capture = Some(self.capture_clocks());
+                }
+            }
+            1..REGISTERS => self.registers[index] = value,
+            _ => {}
+        }
+        false
+    }
+
+    fn tick(&mut self, clocks: u32) {
+        let Some(remaining) = self.capture else {
+            return;
+        };
+        match remaining.checked_sub(clocks) {
+            Some(remaining) if remaining > 0 => self.capture = Some(remaining),
+            _ => {
+                self.capture = None;
+                self.finish_capture();
+            }
+        }
+    }
+
+    fn set_camera_frames(&mut self, frames: Vec<Box<[u8]>>) -> bool {
+        self.frames = frames;
+        self.frame = 0;
+        true
+    }
+
+    fn save(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load(&mut self, save: &[u8]) -> bool {
+        load_ram(&mut self.ram, save)
+    }
+}
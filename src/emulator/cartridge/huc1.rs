@@ -0,0 +1,73 @@
+use super::{
+    load_ram, read_ram_bank, read_rom_bank, write_ram_bank, Cartridge,
+};
+
+/// Hudson's mapper with an infrared port, up to 1 MiB of ROM and 32 KiB of
+/// RAM. With nothing on the other end of the infrared link the sensor never
+/// sees light.
+pub struct HuC1 {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    /// 0xA000-0xBFFF is the infrared port instead of RAM.
+    infrared: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+}
+
+impl HuC1 {
+    pub fn new(rom: Box<[u8]>, ram: Box<[u8]>) -> Self {
+        Self {
+            rom,
+            ram,
+            infrared: false,
+            rom_bank: 1,
+            ram_bank: 0,
+        }
+    }
+}
+
+impl Cartridge for HuC1 {
+    fn read_rom(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => 0,
+            _ => self.rom_bank as usize,
+        };
+        read_rom_bank(&self.rom, bank, address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.infrared = value & 0x0F == 0x0E,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x3F,
+            0x4000..=0x5FFF => self.ram_bank = value & 0b11,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> u8 {
+        if self.infrared {
+            // bit 0 is the receiver, set while it sees light
+            return 0xC0;
+        }
+        read_ram_bank(&self.ram, self.ram_bank as usize, address)
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        // the infrared LED has nobody to talk to
+        !self.infrared
+            && write_ram_bank(
+                &mut self.ram,
+                self.ram_bank as usize,
+                address,
+                value,
+            )
+    }
+
+    fn save(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load(&mut self, save: &[u8]) -> bool {
+        load_ram(&mut self.ram, save)
+    }
+}
@@ -0,0 +1,189 @@
+use super::{
+    read_ram_bank, read_rom_bank, unix_time, write_ram_bank, Cartridge,
+};
+
+/// 4 MiHz clocks in a minute, the clock only counts whole minutes.
+const CLOCKS_PER_MINUTE: u32 = 60 * 4 * 1024 * 1024;
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// Size of the footer appended to the RAM in `.sav` files, the minute of the
+/// day and the day counter as 16 bit little endian words, then the UNIX time
+/// of the save as a 64 bit word.
+const FOOTER_SIZE: usize = 12;
+
+/// Hudson's mapper with an infrared port and a clock, up to 2 MiB of ROM and
+/// 128 KiB of RAM.
+///
+/// The clock is driven through a nibble wide command register and keeps its
+/// time in a small nibble memory the game reads and writes it through.
+pub struct HuC3 {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    /// What 0xA000-0xBFFF is mapped to, the value written to 0x0000-0x1FFF.
+    mode: u8,
+    rom_bank: u8,
+    ram_bank: u8,
+    /// The clock's nibble memory, the time is copied in and out of it.
+    memory: [u8; 0x100],
+    address: u8,
+    /// Last command written, reads in mode 0x0C return it with the result.
+    command: u8,
+    result: u8,
+    minutes: u16,
+    days: u16,
+    clocks: u32,
+}
+
+impl HuC3 {
+    pub fn new(rom: Box<[u8]>, ram: Box<[u8]>) -> Self {
+        Self {
+            rom,
+            ram,
+            mode: 0,
+            rom_bank: 1,
+            ram_bank: 0,
+            memory: [0; 0x100],
+            address: 0,
+            command: 0,
+            result: 0,
+            minutes: 0,
+            days: 0,
+            clocks: 0,
+        }
+    }
+
+    fn advance(&mut self, minutes: u64) {
+        let total = self.minutes as u64 + minutes;
+        self.minutes = (total % MINUTES_PER_DAY as u64) as u16;
+        let days = self.days as u64 + total / MINUTES_PER_DAY as u64;
+        self.days = (days & 0x0FFF) as u16;
+    }
+
+    /// Runs a command, returns whether it set the clock.
+    fn execute(&mut self, value: u8) -> bool {
+        let argument = value & 0x0F;
+        self.command = (value >> 4) & 0b111;
+
+        match self.command {
+            // read and write the nibble memory, moving the address along
+            0x1 => {
+                self.result = self.memory[self.address as usize];
+                self.address = self.address.wrapping_add(1);
+            }
+            0x3 => {
+                self.memory[self.address as usize] = argument;
+                self.address = self.address.wrapping_add(1);
+            }
+            0x4 => self.address = (self.address & 0xF0) | argument,
+            0x5 => self.address = (self.address & 0x0F) | argument << 4,
+            0x6 => match argument {
+                // copy the time out to, or in from, nibbles 0x00-0x05
+                0x0 => {
+                    let time = (self.days as u32) << 12 | self.minutes as u32;
+                    for nibble in 0..6 {
+                        self.memory[nibble] =
+                            (time >> (4 * nibble)) as u8 & 0xF;
+                    }
+                }
+                0x1 => {
+                    let time = (0..6).fold(0u32, |time, nibble| {
+                        time | (self.memory[nibble] as u32) << (4 * nibble)
+                    });
+                    self.minutes = (time & 0x0FFF) as u16 % MINUTES_PER_DAY;
+                    self.days = (time >> 12) as u16;
+                    self.clocks = 0;
+                    return true;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        false
+    }
+}
+
+impl Cartridge for HuC3 {
+    fn read_rom(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => 0,
+            _ => self.rom_bank as usize,
+        };
+        read_rom_bank(&self.rom, bank, address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.mode = value & 0x0F,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x7F,
+            0x4000..=0x5FFF => self.ram_bank = value & 0x0F,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> u8 {
+        match self.mode {
+            0x0 | 0xA => {
+                read_ram_bank(&self.ram, self.ram_bank as usize, address)
+            }
+            0xC => 0x80 | self.command << 4 | self.result,
+            // the clock is always ready for the next command
+            0xD => 0x01,
+            // the infrared receiver never sees light
+            0xE => 0xC0,
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        match self.mode {
+            0xA => write_ram_bank(
+                &mut self.ram,
+                self.ram_bank as usize,
+                address,
+                value,
+            ),
+            0xB => self.execute(value),
+            _ => false,
+        }
+    }
+
+    fn tick(&mut self, clocks: u32) {
+        self.clocks += clocks;
+        if self.clocks >= CLOCKS_PER_MINUTE {
+            self.clocks -= CLOCKS_PER_MINUTE;
+            self.advance(1);
+        }
+    }
+
+    fn save(&self) -> Vec<u8> {
+        let mut save = self.ram.to_vec();
+        save.extend_from_slice(&self.minutes.to_le_bytes());
+        save.extend_from_slice(&self.days.to_le_bytes());
+        save.extend_from_slice(&unix_time().to_le_bytes());
+        save
+    }
+
+    fn load(&mut self, save: &[u8]) -> bool {
+        let Some((ram, footer)) = save.split_at_checked(self.ram.len()) else {
+            return false;
+        };
+
+        match footer.len() {
+            0 => {}
+            FOOTER_SIZE => {
+                let word = |index: usize| {
+                    u16::from_le_bytes([footer[index], footer[index + 1]])
+                };
+                let timestamp =
+                    u64::from_le_bytes(footer[4..12].try_into().unwrap());
+                self.minutes = word(0) % MINUTES_PER_DAY;
+                self.days = word(2) & 0x0FFF;
+                self.advance(unix_time().saturating_sub(timestamp) / 60);
+            }
+            _ => return false,
+        }
+        self.ram.copy_from_slice(ram);
+
+        true
+    }
+}
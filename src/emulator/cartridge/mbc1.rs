@@ -0,0 +1,214 @@
+use super::{
+    load_ram, read_ram_bank, read_rom_bank, write_ram_bank, Cartridge,
+    ROM_BANK_SIZE,
+};
+
+/// Up to 2 MiB of ROM and 32 KiB of RAM.
+///
+/// BANK2 either extends the ROM bank number or selects the RAM bank, in
+/// banking mode 1 it also banks 0x0000-0x3FFF and the RAM.
+pub struct Mbc1 {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    ram_enable: bool,
+    /// 5 bit ROM bank register, 0 reads as 1.
+    bank1: u8,
+    /// 2 bit upper ROM bank or RAM bank register.
+    bank2: u8,
+    mode: bool,
+    /// 1 MiB multicarts leave bit 4 of BANK1 unconnected and wire BANK2 to
+    /// ROM address lines 18 and 19 instead of 19 and 20.
+    multicart: bool,
+}
+
+impl Mbc1 {
+    pub fn new(rom: Box<[u8]>, ram: Box<[u8]>) -> Self {
+        let multicart = is_multicart(&rom);
+        Self {
+            rom,
+            ram,
+            ram_enable: false,
+            bank1: 1,
+            bank2: 0,
+            mode: false,
+            multicart,
+        }
+    }
+
+    fn bank2_shift(&self) -> u8 {
+        if self.multicart {
+            4
+        } else {
+            5
+        }
+    }
+
+    fn ram_bank(&self) -> usize {
+        if self.mode {
+            self.bank2 as usize
+        } else {
+            0
+        }
+    }
+}
+
+impl Cartridge for Mbc1 {
+    fn read_rom(&self, address: u16) -> u8 {
+        let upper = (self.bank2 as usize) << self.bank2_shift();
+        let bank = match address {
+            0x0000..=0x3FFF if self.mode => upper,
+            0x0000..=0x3FFF => 0,
+            _ if self.multicart => upper | (self.bank1 & 0x0F) as usize,
+            _ => upper | self.bank1 as usize,
+        };
+        read_rom_bank(&self.rom, bank, address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enable = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => {
+                // the zero check looks at all 5 bits, even the ones a
+                // multicart leaves unconnected
+                self.bank1 = (value & 0x1F).max(1);
+            }
+            0x4000..=0x5FFF => self.bank2 = value & 0b11,
+            _ => self.mode = value & 0b1 != 0,
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> u8 {
+        if !self.ram_enable {
+            return 0xFF;
+        }
+        read_ram_bank(&self.ram, self.ram_bank(), address)
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        if !self.ram_enable {
+            return false;
+        }
+        let bank = self.ram_bank();
+        write_ram_bank(&mut self.ram, bank, address, value)
+    }
+
+    fn save(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load(&mut self, save: &[u8]) -> bool {
+        load_ram(&mut self.ram, save)
+    }
+}
+
+/// Multicarts are 1 MiB and have a second Nintendo logo at the start of the
+/// game in 256 KiB block 1, there is no header flag for them.
+fn is_multicart(rom: &[u8]) -> bool {
+    const LOGO: std::ops::Range<usize> = 0x0104..0x0134;
+    const GAME: usize = 0x10 * ROM_BANK_SIZE;
+
+    rom.len() == 0x10_0000
+        && rom[GAME + LOGO.start..GAME + LOGO.end] == rom[LOGO]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::cartridge::RAM_BANK_SIZE;
+
+    const LOGO: [u8; 48] = [0xCE; 48];
+
+    /// A ROM of `banks` banks, each starting with its own bank number.
+    fn rom(banks: usize) -> Box<[u8]> {
+        let mut rom = vec![0; banks * ROM_BANK_SIZE];
+        for (bank, data) in rom.chunks_mut(ROM_BANK_SIZE).enumerate() {
+            data[0] = bank as u8;
+        }
+        rom[0x0104..0x0134].copy_from_slice(&LOGO);
+        rom.into_boxed_slice()
+    }
+
+    fn multicart() -> Box<[u8]> {
+        let mut rom = rom(0x40);
+        let game = 0x10 * ROM_BANK_SIZE;
+        rom[game + 0x0104..game + 0x0134].copy_from_slice(&LOGO);
+        rom
+    }
+
+    fn banks(mbc: &Mbc1) -> (u8, u8) {
+        (mbc.read_rom(0x0000), mbc.read_rom(0x4000))
+    }
+
+    #[test]
+    fn mode_0_banks_only_the_upper_half() {
+        let mut mbc = Mbc1::new(rom(0x80), Box::new([]));
+        assert_eq!(banks(&mbc), (0x00, 0x01));
+
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(banks(&mbc), (0x00, 0x01));
+        mbc.write_rom(0x2000, 0x25);
+        mbc.write_rom(0x4000, 0x02);
+        assert_eq!(banks(&mbc), (0x00, 0x45));
+
+        // BANK2 does not save bank 0x20 from the zero check
+        mbc.write_rom(0x2000, 0x00);
+        mbc.write_rom(0x4000, 0x01);
+        assert_eq!(banks(&mbc), (0x00, 0x21));
+    }
+
+    #[test]
+    fn mode_1_banks_the_lower_half_and_ram() {
+        let ram = vec![0; 4 * RAM_BANK_SIZE].into_boxed_slice();
+        let mut mbc = Mbc1::new(rom(0x80), ram);
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_rom(0x2000, 0x03);
+        mbc.write_rom(0x4000, 0x03);
+        mbc.write_rom(0x6000, 0x01);
+        assert_eq!(banks(&mbc), (0x60, 0x63));
+
+        assert!(mbc.write_ram(0xA000, 0x42));
+        assert_eq!(mbc.read_ram(0xA000), 0x42);
+        assert_eq!(mbc.ram[3 * RAM_BANK_SIZE], 0x42);
+
+        // mode 0 goes back to RAM bank 0 and ROM bank 0
+        mbc.write_rom(0x6000, 0x00);
+        assert_eq!(mbc.read_ram(0xA000), 0x00);
+        assert_eq!(banks(&mbc), (0x00, 0x63));
+    }
+
+    #[test]
+    fn disabled_ram_is_not_written() {
+        let ram = vec![0; RAM_BANK_SIZE].into_boxed_slice();
+        let mut mbc = Mbc1::new(rom(0x04), ram);
+        assert!(!mbc.write_ram(0xA000, 0x42));
+        assert_eq!(mbc.read_ram(0xA000), 0xFF);
+        mbc.write_rom(0x0000, 0x0A);
+        assert_eq!(mbc.read_ram(0xA000), 0x00);
+    }
+
+    #[test]
+    fn multicart_wires_bank2_to_bank_bit_4() {
+        let mut mbc = Mbc1::new(multicart(), Box::new([]));
+        assert!(mbc.multicart);
+
+        mbc.write_rom(0x2000, 0x12);
+        mbc.write_rom(0x4000, 0x01);
+        assert_eq!(banks(&mbc), (0x00, 0x12));
+        mbc.write_rom(0x4000, 0x03);
+        assert_eq!(banks(&mbc), (0x00, 0x32));
+
+        // each game's bank 0 shows at 0x0000 in mode 1
+        mbc.write_rom(0x6000, 0x01);
+        assert_eq!(banks(&mbc), (0x30, 0x32));
+
+        // bit 4 is not connected but still counts for the zero check
+        mbc.write_rom(0x2000, 0x10);
+        assert_eq!(banks(&mbc), (0x30, 0x30));
+    }
+
+    #[test]
+    fn multicart_needs_a_second_logo() {
+        assert!(!Mbc1::new(rom(0x40), Box::new([])).multicart);
+        assert!(!Mbc1::new(rom(0x80), Box::new([])).multicart);
+    }
+}
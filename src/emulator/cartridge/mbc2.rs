@@ -0,0 +1,67 @@
+use super::{load_ram, read_rom_bank, Cartridge};
+
+/// Up to 256 KiB of ROM and 512 half bytes of RAM built into the mapper.
+pub struct Mbc2 {
+    rom: Box<[u8]>,
+    /// Only the low nibble of each byte exists.
+    ram: Box<[u8]>,
+    ram_enable: bool,
+    /// 4 bit ROM bank register, 0 reads as 1.
+    bank: u8,
+}
+
+impl Mbc2 {
+    pub fn new(rom: Box<[u8]>) -> Self {
+        Self {
+            rom,
+            ram: vec![0; 0x200].into_boxed_slice(),
+            ram_enable: false,
+            bank: 1,
+        }
+    }
+}
+
+impl Cartridge for Mbc2 {
+    fn read_rom(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => 0,
+            _ => self.bank as usize,
+        };
+        read_rom_bank(&self.rom, bank, address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        // address bit 8 picks the register, both live in 0x0000-0x3FFF
+        match address {
+            0x0000..=0x3FFF if address & 0x0100 == 0 => {
+                self.ram_enable = value & 0x0F == 0x0A
+            }
+            0x0000..=0x3FFF => self.bank = (value & 0x0F).max(1),
+            _ => {}
+        }
+    }
+
+    /// The 512 nibbles repeat through all of 0xA000-0xBFFF, the upper half
+    /// of the byte floats high.
+    fn read_ram(&self, address: u16) -> u8 {
+        if !self.ram_enable {
+            return 0xFF;
+        }
+        0xF0 | self.ram[address as usize & 0x01FF]
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        if self.ram_enable {
+            self.ram[address as usize & 0x01FF] = value & 0x0F;
+        }
+        self.ram_enable
+    }
+
+    fn save(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load(&mut self, save: &[u8]) -> bool {
+        load_ram(&mut self.ram, save)
+    }
+}
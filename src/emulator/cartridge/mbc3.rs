@@ -0,0 +1,115 @@
+use super::rtc::Rtc;
+use super::{read_ram_bank, read_rom_bank, write_ram_bank, Cartridge};
+
+/// Up to 2 MiB of ROM and 32 KiB of RAM, the MBC30 variant in Japanese
+/// Pokémon Crystal doubles both, which the full 8 bit registers cover.
+pub struct Mbc3 {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    rtc: Option<Rtc>,
+    /// Enables both the RAM and the clock registers.
+    ram_enable: bool,
+    /// ROM bank register, 0 reads as 1.
+    rom_bank: u8,
+    /// RAM bank 0x00-0x07 or clock register 0x08-0x0C.
+    ram_bank: u8,
+}
+
+impl Mbc3 {
+    pub fn new(rom: Box<[u8]>, ram: Box<[u8]>, rtc: bool) -> Self {
+        Self {
+            rom,
+            ram,
+            rtc: rtc.then(Rtc::new),
+            ram_enable: false,
+            rom_bank: 1,
+            ram_bank: 0,
+        }
+    }
+}
+
+impl Cartridge for Mbc3 {
+    fn read_rom(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => 0,
+            _ => self.rom_bank as usize,
+        };
+        read_rom_bank(&self.rom, bank, address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enable = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = value.max(1),
+            0x4000..=0x5FFF => self.ram_bank = value & 0x0F,
+            _ => {
+                if let Some(rtc) = &mut self.rtc {
+                    rtc.latch(value);
+                }
+            }
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> u8 {
+        match (self.ram_bank, &self.rtc) {
+            _ if !self.ram_enable => 0xFF,
+            (0x00..=0x07, _) => {
+                read_ram_bank(&self.ram, self.ram_bank as usize, address)
+            }
+            (0x08..=0x0C, Some(rtc)) => rtc.read(self.ram_bank),
+            _ => 0xFF,
+        }
+    }
+
+    /// The clock registers count, the clock is saved too.
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        match (self.ram_bank, &mut self.rtc) {
+            _ if !self.ram_enable => false,
+            (0x00..=0x07, _) => write_ram_bank(
+                &mut self.ram,
+                self.ram_bank as usize,
+                address,
+                value,
+            ),
+            (0x08..=0x0C, Some(rtc)) => {
+                rtc.write(self.ram_bank, value);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn tick(&mut self, clocks: u32) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.tick(clocks);
+        }
+    }
+
+    fn save(&self) -> Vec<u8> {
+        let mut save = self.ram.to_vec();
+        if let Some(rtc) = &self.rtc {
+            save.extend(rtc.save());
+        }
+        save
+    }
+
+    /// A save without the clock footer still restores the RAM, the clock
+    /// then starts from zero.
+    fn load(&mut self, save: &[u8]) -> bool {
+        let Some((ram, footer)) = save.split_at_checked(self.ram.len()) else {
+            return false;
+        };
+
+        if !footer.is_empty() {
+            let Some(rtc) = &mut self.rtc else {
+                return false;
+            };
+            if !rtc.load(footer) {
+                return false;
+            }
+        }
+        self.ram.copy_from_slice(ram);
+
+        true
+    }
+}
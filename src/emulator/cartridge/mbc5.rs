@@ -0,0 +1,75 @@
+use super::{
+    load_ram, read_ram_bank, read_rom_bank, write_ram_bank, Cartridge,
+};
+
+/// Up to 8 MiB of ROM and 128 KiB of RAM, with a 9 bit ROM bank number that,
+/// unlike the older mappers, can select bank 0.
+pub struct Mbc5 {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    ram_enable: bool,
+    rom_bank: u16,
+    ram_bank: u8,
+}
+
+impl Mbc5 {
+    pub fn new(rom: Box<[u8]>, ram: Box<[u8]>) -> Self {
+        Self {
+            rom,
+            ram,
+            ram_enable: false,
+            rom_bank: 1,
+            ram_bank: 0,
+        }
+    }
+}
+
+impl Cartridge for Mbc5 {
+    fn read_rom(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => 0,
+            _ => self.rom_bank as usize,
+        };
+        read_rom_bank(&self.rom, bank, address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enable = value & 0x0F == 0x0A,
+            0x2000..=0x2FFF => {
+                self.rom_bank = (self.rom_bank & 0x100) | value as u16
+            }
+            0x3000..=0x3FFF => {
+                self.rom_bank =
+                    (self.rom_bank & 0x0FF) | ((value as u16 & 0b1) << 8)
+            }
+            0x4000..=0x5FFF => self.ram_bank = value & 0x0F,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> u8 {
+        if !self.ram_enable {
+            return 0xFF;
+        }
+        read_ram_bank(&self.ram, self.ram_bank as usize, address)
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        self.ram_enable
+            && write_ram_bank(
+                &mut self.ram,
+                self.ram_bank as usize,
+                address,
+                value,
+            )
+    }
+
+    fn save(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load(&mut self, save: &[u8]) -> bool {
+        load_ram(&mut self.ram, save)
+    }
+}
@@ -0,0 +1,239 @@
+use super::Cartridge;
+
+/// MBC6 banks ROM, flash and RAM in halves of the usual windows.
+const HALF_ROM_BANK_SIZE: usize = 0x2000;
+const HALF_RAM_BANK_SIZE: usize = 0x1000;
+const FLASH_SIZE: usize = 0x10_0000;
+const FLASH_SECTOR_SIZE: usize = 0x2_0000;
+
+/// The MBC6 in Net de Get, 1 MiB of ROM, 1 MiB of flash and 32 KiB of RAM.
+///
+/// 0x4000-0x5FFF and 0x6000-0x7FFF are banked separately, each can show ROM
+/// or flash, and 0xA000-0xAFFF and 0xB000-0xBFFF each show their own RAM
+/// bank.
+pub struct Mbc6 {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    flash: Flash,
+    ram_enable: bool,
+    ram_banks: [u8; 2],
+    rom_banks: [u8; 2],
+    /// Whether each ROM half shows flash instead of ROM.
+    flash_mapped: [bool; 2],
+    flash_enable: bool,
+}
+
+impl Mbc6 {
+    pub fn new(rom: Box<[u8]>, ram: Box<[u8]>) -> Self {
+        Self {
+            rom,
+            ram,
+            flash: Flash::new(),
+            ram_enable: false,
+            ram_banks: [0; 2],
+            rom_banks: [0; 2],
+            flash_mapped: [false; 2],
+            flash_enable: false,
+        }
+    }
+
+    /// Which half of 0x4000-0x7FFF the address is in, and its offset into
+    /// the selected bank.
+    fn rom_half(&self, address: u16) -> (usize, usize) {
+        let half = (address as usize - 0x4000) / HALF_ROM_BANK_SIZE;
+        let bank = self.rom_banks[half] as usize;
+        (
+            half,
+            bank * HALF_ROM_BANK_SIZE + (address as usize & 0x1FFF),
+        )
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        if !self.ram_enable || self.ram.is_empty() {
+            return None;
+        }
+        let half = (address as usize - 0xA000) / HALF_RAM_BANK_SIZE;
+        let bank = self.ram_banks[half] as usize;
+        let offset = bank * HALF_RAM_BANK_SIZE + (address as usize & 0x0FFF);
+        Some(offset % self.ram.len())
+    }
+}
+
+impl Cartridge for Mbc6 {
+    fn read_rom(&self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => self.rom[address as usize % self.rom.len()],
+            _ => {
+                let (half, offset) = self.rom_half(address);
+                if self.flash_mapped[half] {
+                    self.flash.read(offset)
+                } else {
+                    self.rom[offset % self.rom.len()]
+                }
+            }
+        }
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x03FF => self.ram_enable = value & 0x0F == 0x0A,
+            0x0400..=0x07FF => self.ram_banks[0] = value & 0b111,
+            0x0800..=0x0BFF => self.ram_banks[1] = value & 0b111,
+            0x0C00..=0x0FFF => self.flash_enable = value & 0b1 != 0,
+            0x1000 => self.flash.write_enable = value & 0b1 != 0,
+            0x2000..=0x27FF => self.rom_banks[0] = value & 0x7F,
+            0x2800..=0x2FFF => self.flash_mapped[0] = value == 0x08,
+            0x3000..=0x37FF => self.rom_banks[1] = value & 0x7F,
+            0x3800..=0x3FFF => self.flash_mapped[1] = value == 0x08,
+            0x4000..=0x7FFF => {
+                let (half, offset) = self.rom_half(address);
+                if self.flash_mapped[half] && self.flash_enable {
+                    self.flash.write(offset, value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> u8 {
+        self.ram_offset(address)
+            .map_or(0xFF, |offset| self.ram[offset])
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        let Some(offset) = self.ram_offset(address) else {
+            return false;
+        };
+        self.ram[offset] = value;
+        true
+    }
+
+    /// The RAM followed by the whole flash.
+    fn save(&self) -> Vec<u8> {
+        let mut save = self.ram.to_vec();
+        save.extend_from_slice(&self.flash.data);
+        save
+    }
+
+    fn load(&mut self, save: &[u8]) -> bool {
+        if save.len() != self.ram.len() + FLASH_SIZE {
+            return false;
+        }
+        let (ram, flash) = save.split_at(self.ram.len());
+        self.ram.copy_from_slice(ram);
+        self.flash.data.copy_from_slice(flash);
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlashState {
+    Read,
+    /// Bytes of the 0xAA, 0x55 unlock sequence seen so far.
+    Unlock(u8),
+    /// Unlocked, waiting for the command byte.
+    Command,
+    Program,
+    /// 0x80 was sent, an erase needs a second unlock.
+    EraseUnlock(u8),
+    Erase,
+    Id,
+}
+
+/// A 1 MiB Macronix flash chip, programmed through the usual JEDEC style
+/// command sequences. The chip's command addresses are not checked, only the
+/// sequence of bytes.
+struct Flash {
+    data: Box<[u8]>,
+    state: FlashState,
+    /// The MBC's own write protect, separate from the chip's commands.
+    write_enable: bool,
+}
+
+impl Flash {
+    fn new() -> Self {
+        Self {
+            data: vec![0xFF; FLASH_SIZE].into_boxed_slice(),
+            state: FlashState::Read,
+            write_enable: false,
+        }
+    }
+
+    fn read(&self, offset: usize) -> u8 {
+        match self.state {
+            // manufacturer and device ID
+            FlashState::Id => match offset & 0b1 {
+                0 => 0xC2,
+                _ => 0x81,
+            },
+            // the chip only reports status once a program or erase command
+            // has been given, and both finish instantly so the status bit
+            // reads as done
+            FlashState::Program | FlashState::Erase => 0x80,
+            _ => self.data[offset % FLASH_SIZE],
+        }
+    }
+
+    fn write(&mut self, offset: usize, value: u8) {
+        use FlashState::*;
+
+        if value == 0xF0 {
+            self.state = Read;
+            return;
+        }
+
+        self.state = match (self.state, value) {
+            (Read | Id, 0xAA) => Unlock(1),
+            (Unlock(1), 0x55) => Command,
+            (Command, 0xA0) => Program,
+            (Command, 0x80) => EraseUnlock(0),
+            (Command, 0x90) => Id,
+            (Program, _) => {
+                if self.write_enable {
+                    // programming can only clear bits
+                    self.data[offset % FLASH_SIZE] &= value;
+                }
+                Read
+            }
+            (EraseUnlock(0), 0xAA) => EraseUnlock(1),
+            (EraseUnlock(1), 0x55) => Erase,
+            (Erase, 0x30) => {
+                if self.write_enable {
+                    let start = offset % FLASH_SIZE / FLASH_SECTOR_SIZE
+                        * FLASH_SECTOR_SIZE;
+                    self.data[start..start + FLASH_SECTOR_SIZE].fill(0xFF);
+                }
+                Read
+            }
+            (Erase, 0x10) => {
+                if self.write_enable {
+                    self.data.fill(0xFF);
+                }
+                Read
+            }
+            _ => Read,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flash_reads_data_until_a_command_is_given() {
+        let mut flash = Flash::new();
+        flash.write_enable = true;
+        flash.data[0x10] = 0x42;
+
+        flash.write(0x5555, 0xAA);
+        assert_eq!(flash.read(0x10), 0x42);
+        flash.write(0x2AAA, 0x55);
+        assert_eq!(flash.read(0x10), 0x42);
+
+        flash.write(0x5555, 0xA0);
+        assert_eq!(flash.read(0x10), 0x80);
+        flash.write(0x10, 0x0F);
+        assert_eq!(flash.read(0x10), 0x02);
+    }
+}
@@ -0,0 +1,299 @@
+use super::{read_rom_bank, Cartridge};
+
+/// Accelerometer reading with the cartridge held flat.
+const TILT_CENTER: f32 = 0x81D0 as f32;
+/// Accelerometer change for a tilt of 1 g.
+const TILT_RANGE: f32 = 0x70 as f32;
+
+const EEPROM_WORDS: usize = 0x80;
+
+/// The MBC7 in Kirby Tilt 'n' Tumble, up to 2 MiB of ROM, a two axis
+/// accelerometer and a 256 byte serial EEPROM in place of RAM.
+pub struct Mbc7 {
+    rom: Box<[u8]>,
+    rom_bank: u8,
+    /// 0xA000-0xAFFF needs both enables.
+    ram_enable: [bool; 2],
+    /// Tilt in g, -1 to 1 on each axis, set by the frontend.
+    tilt: (f32, f32),
+    /// Accelerometer values the game reads, updated by the latch sequence.
+    latched: (u16, u16),
+    /// 0x55 was written to the erase register, 0xAA to the latch register
+    /// samples the accelerometer.
+    erased: bool,
+    eeprom: Eeprom,
+}
+
+impl Mbc7 {
+    pub fn new(rom: Box<[u8]>) -> Self {
+        Self {
+            rom,
+            rom_bank: 1,
+            ram_enable: [false; 2],
+            tilt: (0.0, 0.0),
+            latched: (0x8000, 0x8000),
+            erased: false,
+            eeprom: Eeprom::new(),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.ram_enable[0] && self.ram_enable[1]
+    }
+}
+
+impl Cartridge for Mbc7 {
+    fn read_rom(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => 0,
+            _ => self.rom_bank as usize,
+        };
+        read_rom_bank(&self.rom, bank, address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enable[0] = value == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x7F,
+            0x4000..=0x5FFF => self.ram_enable[1] = value == 0x40,
+            _ => {}
+        }
+    }
+
+    /// The registers repeat every 0x100 bytes of 0xA000-0xAFFF, address bits
+    /// 4-7 pick the register.
+    fn read_ram(&self, address: u16) -> u8 {
+        if !self.enabled() || address >= 0xB000 {
+            return 0xFF;
+        }
+
+        let (x, y) = self.latched;
+        match (address >> 4) & 0x0F {
+            0x2 => x as u8,
+            0x3 => (x >> 8) as u8,
+            0x4 => y as u8,
+            0x5 => (y >> 8) as u8,
+            0x6 => 0x00,
+            0x8 => self.eeprom.read(),
+            _ => 0xFF,
+        }
+    }
+
+    /// Only EEPROM writes count, most of them just clock a bit in.
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        if !self.enabled() || address >= 0xB000 {
+            return false;
+        }
+
+        match (address >> 4) & 0x0F {
+            0x0 if value == 0x55 => {
+                self.erased = true;
+                self.latched = (0x8000, 0x8000);
+            }
+            0x1 if value == 0xAA && self.erased => {
+                self.erased = false;
+                let axis = |tilt: f32| {
+                    (TILT_CENTER + tilt.clamp(-1.0, 1.0) * TILT_RANGE) as u16
+                };
+                self.latched = (axis(self.tilt.0), axis(self.tilt.1));
+            }
+            0x8 => {
+                let data = self.eeprom.data;
+                self.eeprom.write(value);
+                return self.eeprom.data != data;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn set_tilt(&mut self, x: f32, y: f32) {
+        self.tilt = (x, y);
+    }
+
+    /// The EEPROM words, little endian.
+    fn save(&self) -> Vec<u8> {
+        self.eeprom
+            .data
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect()
+    }
+
+    fn load(&mut self, save: &[u8]) -> bool {
+        if save.len() != 2 * EEPROM_WORDS {
+            return false;
+        }
+        for (word, bytes) in self.eeprom.data.iter_mut().zip(save.chunks(2)) {
+            *word = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum EepromState {
+    /// Waiting for a start bit.
+    Idle,
+    /// Shifting in the 2 bit opcode and 8 bit address after the start bit.
+    Command { bits: u16, count: u8 },
+    /// Shifting out a word, MSB first.
+    Read { word: u16, count: u8 },
+    /// Shifting in a word for one address, or all of them.
+    Write {
+        address: Option<u8>,
+        word: u16,
+        count: u8,
+    },
+    /// The command finished, DO reads 1 until CS goes low.
+    Done,
+}
+
+/// A 93LC56 serial EEPROM in 16 bit mode, driven by bit banging CS (bit 7),
+/// CLK (bit 6) and DI (bit 1), with DO on bit 0.
+struct Eeprom {
+    data: [u16; EEPROM_WORDS],
+    state: EepromState,
+    write_enable: bool,
+    cs: bool,
+    clk: bool,
+    di: bool,
+    out: bool,
+}
+
+impl Eeprom {
+    fn new() -> Self {
+        Self {
+            data: [0xFFFF; EEPROM_WORDS],
+            state: EepromState::Idle,
+            write_enable: false,
+            cs: false,
+            clk: false,
+            di: false,
+            out: true,
+        }
+    }
+
+    fn read(&self) -> u8 {
+        (self.cs as u8) << 7
+            | (self.clk as u8) << 6
+            | (self.di as u8) << 1
+            | self.out as u8
+    }
+
+    fn write(&mut self, value: u8) {
+        let cs = value & 0b1000_0000 != 0;
+        let clk = value & 0b0100_0000 != 0;
+        self.di = value & 0b0000_0010 != 0;
+
+        if !cs {
+            self.state = EepromState::Idle;
+            self.out = true;
+        } else if clk && !self.clk {
+            self.clock();
+        }
+
+        self.cs = cs;
+        self.clk = clk;
+    }
+
+    /// A rising clock edge with CS high.
+    fn clock(&mut self) {
+        use EepromState::*;
+        let di = self.di as u16;
+
+        self.state = match self.state {
+            Idle if self.di => Command { bits: 0, count: 0 },
+            Idle => Idle,
+            Command { bits, count } if count < 9 => Command {
+                bits: bits << 1 | di,
+                count: count + 1,
+            },
+            Command { bits, .. } => self.command(bits << 1 | di),
+            Read { word, count } => {
+                self.out = word & (1 << (15 - count)) != 0;
+                if count == 15 {
+                    Done
+                } else {
+                    Read {
+                        word,
+                        count: count + 1,
+                    }
+                }
+            }
+            Write {
+                address,
+                word,
+                count,
+            } => {
+                let word = word << 1 | di;
+                if count < 15 {
+                    Write {
+                        address,
+                        word,
+                        count: count + 1,
+                    }
+                } else {
+                    if self.write_enable {
+                        match address {
+                            Some(address) => self.data[address as usize] = word,
+                            None => self.data.fill(word),
+                        }
+                    }
+                    self.out = true;
+                    Done
+                }
+            }
+            Done => Done,
+        };
+    }
+
+    /// Runs the 10 bits after the start bit, the opcode and the address.
+    fn command(&mut self, bits: u16) -> EepromState {
+        let address = (bits as u8) & 0x7F;
+        match (bits >> 8) & 0b11 {
+            0b10 => {
+                // a dummy 0 comes before the data
+                self.out = false;
+                EepromState::Read {
+                    word: self.data[address as usize],
+                    count: 0,
+                }
+            }
+            0b01 => EepromState::Write {
+                address: Some(address),
+                word: 0,
+                count: 0,
+            },
+            0b11 => {
+                if self.write_enable {
+                    self.data[address as usize] = 0xFFFF;
+                }
+                self.out = true;
+                EepromState::Done
+            }
+            _ => match (bits >> 6) & 0b11 {
+                0b00 => {
+                    self.write_enable = false;
+                    EepromState::Done
+                }
+                0b11 => {
+                    self.write_enable = true;
+                    EepromState::Done
+                }
+                0b10 => {
+                    if self.write_enable {
+                        self.data.fill(0xFFFF);
+                    }
+                    self.out = true;
+                    EepromState::Done
+                }
+                _ => EepromState::Write {
+                    address: None,
+                    word: 0,
+                    count: 0,
+                },
+            },
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use super::{
+    load_ram, read_ram_bank, read_rom_bank, write_ram_bank, Cartridge,
+};
+
+/// The MMM01 in multi game compilations, up to 8 MiB of ROM and 128 KiB of
+/// RAM.
+///
+/// It starts out showing the last 32 KiB of the ROM, where the menu lives.
+/// The menu sets the outer bank bits and masks for the chosen game, then sets
+/// the map bit, which locks them and hands the game what looks like an MBC1.
+pub struct Mmm01 {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    mapped: bool,
+    ram_enable: bool,
+    /// 9 bits, the low 5 are the game's own, the rest are set by the menu.
+    rom_bank: u16,
+    /// Bits 1-4 of the ROM bank the game cannot change, set by the menu.
+    rom_mask: u16,
+    /// 4 bits, the low 2 are the game's own, the rest are set by the menu.
+    ram_bank: u8,
+    /// Bits 0-1 of the RAM bank the game cannot change, set by the menu.
+    ram_mask: u8,
+}
+
+impl Mmm01 {
+    pub fn new(rom: Box<[u8]>, ram: Box<[u8]>) -> Self {
+        Self {
+            rom,
+            ram,
+            mapped: false,
+            ram_enable: false,
+            rom_bank: 0,
+            rom_mask: 0,
+            ram_bank: 0,
+            ram_mask: 0,
+        }
+    }
+
+    /// The 5 bit bank register as the game sees it, bits the menu masked
+    /// keep their value.
+    fn set_low_bits(bank: u16, value: u16, bits: u16, mask: u16) -> u16 {
+        let writable = bits & !mask;
+        (bank & !writable) | (value & writable)
+    }
+}
+
+impl Cartridge for Mmm01 {
+    fn read_rom(&self, address: u16) -> u8 {
+        let bank = match (self.mapped, address) {
+            // banks past the end wrap around to the last 32 KiB
+            (false, 0x0000..=0x3FFF) => 0x1FE,
+            (false, _) => 0x1FF,
+            (true, 0x0000..=0x3FFF) => {
+                (self.rom_bank & !0x1F) | (self.rom_bank & self.rom_mask)
+            }
+            (true, _) if self.rom_bank & 0x1F & !self.rom_mask == 0 => {
+                self.rom_bank | 1
+            }
+            (true, _) => self.rom_bank,
+        };
+        read_rom_bank(&self.rom, bank as usize, address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        let mapped = self.mapped;
+        match address {
+            0x0000..=0x1FFF => {
+                self.ram_enable = value & 0x0F == 0x0A;
+                if !mapped {
+                    self.ram_mask = (value >> 4) & 0b11;
+                    self.mapped = value & 0b0100_0000 != 0;
+                }
+            }
+            0x2000..=0x3FFF => {
+                let (bits, mask) = match mapped {
+                    true => (0x1F, self.rom_mask),
+                    false => (0x7F, 0),
+                };
+                self.rom_bank =
+                    Self::set_low_bits(self.rom_bank, value as u16, bits, mask);
+            }
+            0x4000..=0x5FFF => {
+                let (bits, mask) = match mapped {
+                    true => (0b11, self.ram_mask as u16),
+                    false => (0b1111, 0),
+                };
+                self.ram_bank = Self::set_low_bits(
+                    self.ram_bank as u16,
+                    value as u16,
+                    bits,
+                    mask,
+                ) as u8;
+                if !mapped {
+                    let high = ((value as u16 >> 4) & 0b11) << 7;
+                    self.rom_bank = (self.rom_bank & 0x7F) | high;
+                }
+            }
+            0x6000..=0x7FFF if !mapped => {
+                self.rom_mask = ((value as u16 >> 2) & 0x0F) << 1;
+            }
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> u8 {
+        if !self.ram_enable {
+            return 0xFF;
+        }
+        read_ram_bank(&self.ram, self.ram_bank as usize, address)
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        if !self.ram_enable {
+            return false;
+        }
+        let bank = self.ram_bank as usize;
+        write_ram_bank(&mut self.ram, bank, address, value)
+    }
+
+    fn save(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load(&mut self, save: &[u8]) -> bool {
+        load_ram(&mut self.ram, save)
+    }
+}
@@ -0,0 +1,112 @@
+use super::{
+    read_ram_bank, read_rom_bank, write_ram_bank, Cartridge, ROM_BANK_SIZE,
+};
+
+/// Games in a multicart start on 32 KiB boundaries.
+const GAME_ALIGN: usize = 0x8000;
+const LOGO: std::ops::Range<usize> = 0x0104..0x0134;
+const TITLE: std::ops::Range<usize> = 0x0134..0x0143;
+
+/// The pirate "N in 1" multicarts, a menu followed by a set of MBC1 and 32
+/// KiB games.
+///
+/// The menu runs from the start of the ROM. Once a game is picked the menu
+/// writes its offset, in 32 KiB units, to 0x6000-0x7FFF, which also locks
+/// that register until power off. From then on the game sees an MBC1 whose
+/// banks wrap at the size in the game's own header.
+pub struct Multicart {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    ram_enable: bool,
+    /// The selected game's first 16 KiB bank.
+    base_bank: usize,
+    /// Mask of the banks that make up the selected game.
+    bank_mask: usize,
+    locked: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+}
+
+impl Multicart {
+    pub fn new(rom: Box<[u8]>, ram: Box<[u8]>) -> Self {
+        let bank_mask = game_banks(&rom, 0) - 1;
+        Self {
+            rom,
+            ram,
+            ram_enable: false,
+            base_bank: 0,
+            bank_mask,
+            locked: false,
+            rom_bank: 1,
+            ram_bank: 0,
+        }
+    }
+}
+
+impl Cartridge for Multicart {
+    fn read_rom(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => 0,
+            _ => self.rom_bank as usize & self.bank_mask,
+        };
+        read_rom_bank(&self.rom, self.base_bank + bank, address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enable = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = (value & 0x1F).max(1),
+            0x4000..=0x5FFF => self.ram_bank = value & 0b11,
+            0x6000..=0x7FFF if !self.locked => {
+                let offset = value as usize * GAME_ALIGN;
+                self.base_bank = offset / ROM_BANK_SIZE;
+                self.bank_mask = game_banks(&self.rom, offset) - 1;
+                self.locked = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, address: u16) -> u8 {
+        if !self.ram_enable {
+            return 0xFF;
+        }
+        read_ram_bank(&self.ram, self.ram_bank as usize, address)
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        if !self.ram_enable {
+            return false;
+        }
+        let bank = self.ram_bank as usize;
+        write_ram_bank(&mut self.ram, bank, address, value)
+    }
+}
+
+/// 16 KiB banks in the game at `offset`, from the ROM size in its header.
+fn game_banks(rom: &[u8], offset: usize) -> usize {
+    match rom.get(offset + 0x0148) {
+        Some(&code @ 0x00..=0x08) => 2 << code,
+        _ => 2,
+    }
+}
+
+/// The headers of pirate multicarts describe the menu, the ROM is bigger
+/// than they say and there are more games, each with a logo of its own, at
+/// 32 KiB boundaries further in. Overdumps repeat the same header, and MBC1
+/// multicarts are left to the MBC1.
+pub fn detect(rom: &[u8], cartridge_type: u8, rom_size: usize) -> bool {
+    let (Some(logo), Some(title)) = (rom.get(LOGO), rom.get(TITLE)) else {
+        return false;
+    };
+    let game = |offset: usize, range: &std::ops::Range<usize>| {
+        rom.get(offset + range.start..offset + range.end)
+    };
+
+    !matches!(cartridge_type, 0x01..=0x03)
+        && rom.len() > rom_size
+        && (GAME_ALIGN..rom.len()).step_by(GAME_ALIGN).any(|offset| {
+            game(offset, &LOGO) == Some(logo)
+                && game(offset, &TITLE) != Some(title)
+        })
+}
@@ -0,0 +1,39 @@
+use super::{
+    load_ram, read_ram_bank, read_rom_bank, write_ram_bank, Cartridge,
+};
+
+/// No mapper, 32 KiB of ROM and optionally up to 8 KiB of RAM.
+pub struct Rom {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+}
+
+impl Rom {
+    pub fn new(rom: Box<[u8]>, ram: Box<[u8]>) -> Self {
+        Self { rom, ram }
+    }
+}
+
+impl Cartridge for Rom {
+    fn read_rom(&self, address: u16) -> u8 {
+        read_rom_bank(&self.rom, (address >> 14) as usize, address)
+    }
+
+    fn write_rom(&mut self, _address: u16, _value: u8) {}
+
+    fn read_ram(&self, address: u16) -> u8 {
+        read_ram_bank(&self.ram, 0, address)
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        write_ram_bank(&mut self.ram, 0, address, value)
+    }
+
+    fn save(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load(&mut self, save: &[u8]) -> bool {
+        load_ram(&mut self.ram, save)
+    }
+}
@@ -0,0 +1,328 @@
+use super::unix_time;
+
+/// 4 MiHz clocks in a second, the clock crystal runs at a fixed rate whatever
+/// speed the CPU is in.
+const CLOCKS_PER_SECOND: u32 = 4 * 1024 * 1024;
+
+/// Size of the footer appended to `.sav` files, the 5 clock registers then the
+/// 5 latched ones as 32 bit little endian words, then the UNIX time of the
+/// save as a 64 bit word.
+pub const FOOTER_SIZE: usize = 48;
+
+/// The older 44 byte footer only has a 32 bit timestamp.
+const SHORT_FOOTER_SIZE: usize = 44;
+
+const DAY_HIGH: u8 = 0b0000_0001;
+const HALT: u8 = 0b0100_0000;
+const DAY_CARRY: u8 = 0b1000_0000;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Registers {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    /// Low 8 bits of the day counter.
+    day_low: u8,
+    /// Day counter bit 8, halt and day counter carry.
+    day_high: u8,
+}
+
+impl Registers {
+    fn read(&self, select: u8) -> u8 {
+        match select {
+            0x08 => self.seconds,
+            0x09 => self.minutes,
+            0x0A => self.hours,
+            0x0B => self.day_low,
+            _ => self.day_high,
+        }
+    }
+
+    fn days(&self) -> u32 {
+        ((self.day_high & DAY_HIGH) as u32) << 8 | self.day_low as u32
+    }
+
+    fn set_days(&mut self, days: u32) {
+        self.day_low = days as u8;
+        self.day_high =
+            (self.day_high & !DAY_HIGH) | ((days >> 8) as u8 & DAY_HIGH);
+    }
+
+    /// Every register holds a value the clock can count to, the ones a game
+    /// wrote out of range count up to the register width before wrapping.
+    fn in_range(&self) -> bool {
+        self.seconds < 60 && self.minutes < 60 && self.hours < 24
+    }
+
+    fn tick_second(&mut self) {
+        self.seconds = (self.seconds + 1) & 0x3F;
+        if self.seconds != 60 {
+            return;
+        }
+        self.seconds = 0;
+
+        self.minutes = (self.minutes + 1) & 0x3F;
+        if self.minutes != 60 {
+            return;
+        }
+        self.minutes = 0;
+
+        self.hours = (self.hours + 1) & 0x1F;
+        if self.hours != 24 {
+            return;
+        }
+        self.hours = 0;
+
+        self.add_days(1);
+    }
+
+    fn add_days(&mut self, days: u32) {
+        let days = self.days() + days;
+        if days > 0x1FF {
+            self.day_high |= DAY_CARRY;
+        }
+        self.set_days(days % 0x200);
+    }
+
+    fn advance(&mut self, mut seconds: u64) {
+        while seconds > 0 && !self.in_range() {
+            self.tick_second();
+            seconds -= 1;
+        }
+
+        let total = ((self.hours as u64 * 60) + self.minutes as u64) * 60
+            + self.seconds as u64
+            + seconds;
+        self.seconds = (total % 60) as u8;
+        self.minutes = (total / 60 % 60) as u8;
+        self.hours = (total / 3600 % 24) as u8;
+
+        // the carry stays set once any amount of days overflows
+        let days = total / 86400;
+        if days > 0x1FF {
+            self.day_high |= DAY_CARRY;
+        }
+        self.add_days((days % 0x200) as u32);
+    }
+
+    fn save(&self, footer: &mut Vec<u8>) {
+        for value in [
+            self.seconds,
+            self.minutes,
+            self.hours,
+            self.day_low,
+            self.day_high,
+        ] {
+            footer.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+    }
+
+    fn load(words: &[u8]) -> Registers {
+        let word = |index: usize| words[index * 4];
+        Registers {
+            seconds: word(0) & 0x3F,
+            minutes: word(1) & 0x3F,
+            hours: word(2) & 0x1F,
+            day_low: word(3),
+            day_high: word(4) & (DAY_HIGH | HALT | DAY_CARRY),
+        }
+    }
+}
+
+/// The MBC3 real time clock, mapped to 0xA000-0xBFFF in place of RAM when a
+/// RAM bank number from 0x08 to 0x0C is selected.
+#[derive(Debug, Clone)]
+pub struct Rtc {
+    clock: Registers,
+    /// The copy the CPU reads, updated by the latch sequence.
+    latched: Registers,
+    /// Clocks into the current second.
+    clocks: u32,
+    /// Last value written to the latch register, writing 0 then 1 latches.
+    latch: u8,
+}
+
+impl Rtc {
+    pub fn new() -> Self {
+        Self {
+            clock: Registers::default(),
+            latched: Registers::default(),
+            clocks: 0,
+            latch: 0xFF,
+        }
+    }
+
+    pub fn tick(&mut self, clocks: u32) {
+        if self.clock.day_high & HALT != 0 {
+            return;
+        }
+
+        self.clocks += clocks;
+        if self.clocks >= CLOCKS_PER_SECOND {
+            self.clocks -= CLOCKS_PER_SECOND;
+            self.clock.tick_second();
+        }
+    }
+
+    pub fn latch(&mut self, value: u8) {
+        if self.latch == 0x00 && value == 0x01 {
+            self.latched = self.clock;
+        }
+        self.latch = value;
+    }
+
+    pub fn read(&self, select: u8) -> u8 {
+        self.latched.read(select)
+    }
+
+    /// Writes go to the running clock, the latched copy only changes on the
+    /// next latch.
+    pub fn write(&mut self, select: u8, value: u8) {
+        let clock = &mut self.clock;
+        match select {
+            0x08 => {
+                // writing the seconds restarts the current second
+                self.clocks = 0;
+                clock.seconds = value & 0x3F;
+            }
+            0x09 => clock.minutes = value & 0x3F,
+            0x0A => clock.hours = value & 0x1F,
+            0x0B => clock.day_low = value,
+            _ => clock.day_high = value & (DAY_HIGH | HALT | DAY_CARRY),
+        }
+    }
+
+    pub fn save(&self) -> Vec<u8> {
+        let mut footer = Vec::with_capacity(FOOTER_SIZE);
+        self.clock.save(&mut footer);
+        self.latched.save(&mut footer);
+        footer.extend_from_slice(&unix_time().to_le_bytes());
+        footer
+    }
+
+    /// Restores the clock from a 48 or 44 byte footer and catches up with the
+    /// time that passed since it was saved, returns false if the footer is
+    /// neither size.
+    pub fn load(&mut self, footer: &[u8]) -> bool {
+        let timestamp = match footer.len() {
+            FOOTER_SIZE => {
+                u64::from_le_bytes(footer[40..48].try_into().unwrap())
+            }
+            SHORT_FOOTER_SIZE => {
+                u32::from_le_bytes(footer[40..44].try_into().unwrap()) as u64
+            }
+            _ => return false,
+        };
+
+        self.clock = Registers::load(&footer[0..20]);
+        self.latched = Registers::load(&footer[20..40]);
+        self.clocks = 0;
+
+        if self.clock.day_high & HALT == 0 {
+            self.clock.advance(unix_time().saturating_sub(timestamp));
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seconds, minutes, hours, day low and day high.
+    fn values(registers: &Registers) -> [u8; 5] {
+        [0x08, 0x09, 0x0A, 0x0B, 0x0C].map(|select| registers.read(select))
+    }
+
+    fn set(rtc: &mut Rtc, values: [u8; 5]) {
+        for (select, value) in (0x08..).zip(values) {
+            rtc.write(select, value);
+        }
+    }
+
+    #[test]
+    fn last_second_of_day_511_rolls_over_and_sets_the_carry() {
+        let mut rtc = Rtc::new();
+        set(&mut rtc, [59, 59, 23, 0xFF, DAY_HIGH]);
+        rtc.tick(CLOCKS_PER_SECOND);
+        assert_eq!(values(&rtc.clock), [0, 0, 0, 0, DAY_CARRY]);
+
+        // the carry stays until the game clears it
+        rtc.tick(CLOCKS_PER_SECOND);
+        assert_eq!(values(&rtc.clock), [1, 0, 0, 0, DAY_CARRY]);
+    }
+
+    #[test]
+    fn out_of_range_registers_wrap_at_their_width() {
+        let mut rtc = Rtc::new();
+        set(&mut rtc, [63, 5, 31, 0, 0]);
+        rtc.tick(CLOCKS_PER_SECOND);
+        assert_eq!(values(&rtc.clock), [0, 5, 31, 0, 0]);
+    }
+
+    #[test]
+    fn halted_clock_does_not_count() {
+        let mut rtc = Rtc::new();
+        set(&mut rtc, [10, 0, 0, 0, HALT]);
+        rtc.tick(CLOCKS_PER_SECOND * 3);
+        assert_eq!(values(&rtc.clock), [10, 0, 0, 0, HALT]);
+    }
+
+    #[test]
+    fn reads_see_the_latched_copy() {
+        let mut rtc = Rtc::new();
+        set(&mut rtc, [30, 0, 0, 0, 0]);
+        assert_eq!(rtc.read(0x08), 0);
+
+        // only a 0 followed by a 1 latches
+        rtc.latch(0x01);
+        assert_eq!(rtc.read(0x08), 0);
+        rtc.latch(0x00);
+        rtc.latch(0x01);
+        assert_eq!(rtc.read(0x08), 30);
+
+        rtc.tick(CLOCKS_PER_SECOND);
+        rtc.write(0x09, 12);
+        assert_eq!(rtc.read(0x08), 30);
+        assert_eq!(rtc.read(0x09), 0);
+        rtc.latch(0x00);
+        rtc.latch(0x01);
+        assert_eq!(rtc.read(0x08), 31);
+        assert_eq!(rtc.read(0x09), 12);
+    }
+
+    #[test]
+    fn footer_round_trips() {
+        let mut rtc = Rtc::new();
+        set(&mut rtc, [1, 2, 3, 4, HALT | DAY_HIGH]);
+        rtc.latch(0x00);
+        rtc.latch(0x01);
+        rtc.write(0x08, 5);
+
+        let footer = rtc.save();
+        assert_eq!(footer.len(), FOOTER_SIZE);
+
+        let mut loaded = Rtc::new();
+        assert!(loaded.load(&footer));
+        assert_eq!(values(&loaded.clock), [5, 2, 3, 4, HALT | DAY_HIGH]);
+        assert_eq!(values(&loaded.latched), [1, 2, 3, 4, HALT | DAY_HIGH]);
+
+        assert!(loaded.load(&footer[..SHORT_FOOTER_SIZE]));
+        assert!(!loaded.load(&footer[..FOOTER_SIZE - 1]));
+    }
+
+    #[test]
+    fn loading_catches_up_with_the_time_since_the_save() {
+        let mut rtc = Rtc::new();
+        set(&mut rtc, [0, 0, 0, 0, 0]);
+        let mut footer = rtc.save();
+        let saved = unix_time() - 90 * 60;
+        footer[40..48].copy_from_slice(&saved.to_le_bytes());
+
+        let mut loaded = Rtc::new();
+        assert!(loaded.load(&footer));
+        let [_, minutes, hours, ..] = values(&loaded.clock);
+        assert_eq!((hours, minutes), (1, 30));
+    }
+}
@@ -0,0 +1,144 @@
+use super::super::header::NINTENDO_LOGO;
+use super::{read_rom_bank, Cartridge};
+use std::cell::Cell;
+
+/// Rising edges of A15 each lock stage lasts.
+const LOCK_EDGES: u8 = 0x30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sachen {
+    Mmc1,
+    /// Adds a lock stage for the CGB boot ROM in front of the MMC1's.
+    Mmc2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lock {
+    /// Header reads have A7 forced low, the MMC2 starts here.
+    Cgb,
+    /// Header reads have A7 forced high, so the boot ROM finds the Nintendo
+    /// logo at 0x0184 instead of Sachen's own at 0x0104.
+    Dmg,
+    Unlocked,
+}
+
+/// Sachen's unlicensed mappers, up to 2 MiB of ROM and no RAM.
+///
+/// The header is hidden behind a lock that scrambles the address lines of
+/// reads from 0x0100-0x01FF until A15 has gone high enough times, which is
+/// after the boot ROM is done with the logo. Banking is an inner ROM bank,
+/// and an outer base bank for multicarts with a mask picking which bits come
+/// from which.
+pub struct SachenMmc {
+    rom: Box<[u8]>,
+    base_bank: u8,
+    rom_bank: u8,
+    /// Set bits come from the base bank, clear bits from the ROM bank.
+    bank_mask: u8,
+    lock: Cell<Lock>,
+    edges: Cell<u8>,
+    a15: Cell<bool>,
+}
+
+impl SachenMmc {
+    pub fn new(rom: Box<[u8]>, kind: Sachen) -> Self {
+        let lock = match kind {
+            Sachen::Mmc1 => Lock::Dmg,
+            Sachen::Mmc2 => Lock::Cgb,
+        };
+        Self {
+            rom,
+            base_bank: 0,
+            rom_bank: 1,
+            bank_mask: 0,
+            lock: Cell::new(lock),
+            edges: Cell::new(0),
+            a15: Cell::new(false),
+        }
+    }
+}
+
+impl Cartridge for SachenMmc {
+    fn read_rom(&self, address: u16) -> u8 {
+        let address = match (self.lock.get(), address) {
+            (Lock::Cgb, 0x0100..=0x01FF) => scramble(address) & !0x80,
+            (Lock::Dmg, 0x0100..=0x01FF) => scramble(address) | 0x80,
+            _ => address,
+        };
+
+        let base = self.base_bank & self.bank_mask;
+        let bank = match address {
+            0x0000..=0x3FFF => base,
+            _ => base | (self.rom_bank & !self.bank_mask),
+        };
+        read_rom_bank(&self.rom, bank as usize, address)
+    }
+
+    fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            // the base bank can only be moved while the ROM bank points at
+            // the menu's bank switching code
+            0x0000..=0x1FFF if self.rom_bank & 0x30 == 0x30 => {
+                self.base_bank = value
+            }
+            0x2000..=0x3FFF => self.rom_bank = value.max(1),
+            0x4000..=0x5FFF => self.bank_mask = value,
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, _address: u16) -> u8 {
+        0xFF
+    }
+
+    fn write_ram(&mut self, _address: u16, _value: u8) -> bool {
+        false
+    }
+
+    fn skip_boot_rom(&mut self) {
+        self.lock.set(Lock::Unlocked);
+    }
+
+    fn bus_access(&self, address: u16) {
+        let a15 = address & 0x8000 != 0;
+        let rising = !self.a15.replace(a15) && a15;
+        if !rising || self.lock.get() == Lock::Unlocked {
+            return;
+        }
+
+        let edges = self.edges.get() + 1;
+        if edges < LOCK_EDGES {
+            self.edges.set(edges);
+            return;
+        }
+        self.edges.set(0);
+        self.lock.set(match self.lock.get() {
+            Lock::Cgb => Lock::Dmg,
+            _ => Lock::Unlocked,
+        });
+    }
+}
+
+/// Header reads while locked swap address lines A0 with A6 and A1 with A4.
+fn scramble(address: u16) -> u16 {
+    let bit = |n: u16| (address >> n) & 1;
+    (address & !0b0101_0011) | bit(6) | bit(4) << 1 | bit(1) << 4 | bit(0) << 6
+}
+
+/// Sachen cartridges have their own logo at 0x0104 and the Nintendo logo
+/// scrambled where the locked mapper shows it to the boot ROM. The MMC2 is
+/// the one in CGB compatible cartridges.
+pub fn detect(rom: &[u8]) -> Option<Sachen> {
+    let locked =
+        |address: u16| rom.get((scramble(address) | 0x80) as usize).copied();
+
+    let logo = (0x0104..0x0134).map(locked);
+    if !logo.eq(NINTENDO_LOGO.iter().copied().map(Some)) {
+        return None;
+    }
+
+    match locked(0x0143) {
+        Some(flag) if flag & 0x80 != 0 => Some(Sachen::Mmc2),
+        _ => Some(Sachen::Mmc1),
+    }
+}
@@ -0,0 +1,112 @@
+use super::{load_ram, read_rom_bank, Cartridge};
+
+const RAM_SIZE: usize = 0x20;
+
+/// Bandai's TAMA5 in Game de Hakken!! Tamagotchi 3, up to 512 KiB of ROM and
+/// 32 bytes of battery backed RAM.
+///
+/// Everything goes through two nibble wide ports, 0xA001 selects a register
+/// and 0xA000 reads or writes it. The cartridge's clock chip is not emulated,
+/// only the RAM behind it.
+pub struct Tama5 {
+    rom: Box<[u8]>,
+    ram: Box<[u8]>,
+    register: u8,
+    rom_bank: u8,
+    /// Byte to write, set a nibble at a time through registers 4 and 5.
+    data: u8,
+    /// 5 bit RAM address, the top bit is shared with the command register.
+    address: u8,
+    command: u8,
+    /// Byte read by the last read command, registers 0xC and 0xD.
+    result: u8,
+}
+
+impl Tama5 {
+    pub fn new(rom: Box<[u8]>) -> Self {
+        Self {
+            rom,
+            ram: vec![0; RAM_SIZE].into_boxed_slice(),
+            register: 0,
+            rom_bank: 1,
+            data: 0,
+            address: 0,
+            command: 0,
+            result: 0,
+        }
+    }
+
+    /// Returns whether the write stored a byte in RAM.
+    fn write_register(&mut self, value: u8) -> bool {
+        match self.register {
+            0x0 => self.rom_bank = (self.rom_bank & 0x10) | value,
+            0x1 => self.rom_bank = (self.rom_bank & 0x0F) | (value & 1) << 4,
+            0x4 => self.data = (self.data & 0xF0) | value,
+            0x5 => self.data = (self.data & 0x0F) | value << 4,
+            0x6 => {
+                self.address = (self.address & 0x0F) | (value & 1) << 4;
+                self.command = value >> 1;
+            }
+            // the low address nibble runs the command
+            0x7 => {
+                self.address = (self.address & 0x10) | value;
+                let address = self.address as usize;
+                match self.command {
+                    0x0 => {
+                        self.ram[address] = self.data;
+                        return true;
+                    }
+                    0x1 => self.result = self.ram[address],
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+}
+
+impl Cartridge for Tama5 {
+    fn read_rom(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => 0,
+            _ => self.rom_bank as usize,
+        };
+        read_rom_bank(&self.rom, bank, address)
+    }
+
+    fn write_rom(&mut self, _address: u16, _value: u8) {}
+
+    /// The upper nibble of the ports floats high.
+    fn read_ram(&self, address: u16) -> u8 {
+        if address & 1 != 0 {
+            return 0xFF;
+        }
+        match self.register {
+            // always ready
+            0xA => 0xF1,
+            0xC => 0xF0 | (self.result & 0x0F),
+            0xD => 0xF0 | self.result >> 4,
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) -> bool {
+        let value = value & 0x0F;
+        match address & 1 {
+            0 => self.write_register(value),
+            _ => {
+                self.register = value;
+                false
+            }
+        }
+    }
+
+    fn save(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load(&mut self, save: &[u8]) -> bool {
+        load_ram(&mut self.ram, save)
+    }
+}
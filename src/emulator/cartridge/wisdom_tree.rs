@@ -0,0 +1,48 @@
+use super::Cartridge;
+
+const BANK_SIZE: usize = 0x8000;
+
+/// Wisdom Tree's unlicensed mapper, the whole of 0x0000-0x7FFF is one 32 KiB
+/// bank, picked by the low byte of the address written to in 0x0000-0x3FFF.
+/// The value written does not matter.
+pub struct WisdomTree {
+    rom: Box<[u8]>,
+    bank: u8,
+}
+
+impl WisdomTree {
+    pub fn new(rom: Box<[u8]>) -> Self {
+        Self { rom, bank: 0 }
+    }
+}
+
+impl Cartridge for WisdomTree {
+    fn read_rom(&self, address: u16) -> u8 {
+        let offset = self.bank as usize * BANK_SIZE + address as usize;
+        self.rom[offset % self.rom.len()]
+    }
+
+    fn write_rom(&mut self, address: u16, _value: u8) {
+        if address < 0x4000 {
+            self.bank = address as u8;
+        }
+    }
+
+    fn read_ram(&self, _address: u16) -> u8 {
+        0xFF
+    }
+
+    fn write_ram(&mut self, _address: u16, _value: u8) -> bool {
+        false
+    }
+}
+
+/// Wisdom Tree's headers claim a plain 32 KiB ROM, but the company's name
+/// is in the ROM somewhere and the ROM is bigger than that.
+pub fn detect(rom: &[u8], cartridge_type: u8) -> bool {
+    matches!(cartridge_type, 0x00 | 0xC0)
+        && rom.len() > BANK_SIZE
+        && rom
+            .windows(11)
+            .any(|name| name == b"WISDOM TREE" || name == b"WISDOM\0TREE")
+}
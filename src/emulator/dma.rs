@@ -0,0 +1,272 @@
+/// Bytes copied by an OAM DMA transfer, the whole of OAM.
+const OAM_SIZE: u8 = 0xA0;
+
+/// OAM DMA, started by writing the source address' high byte to DMA (0xFF46).
+///
+/// The transfer starts one M-cycle after the write and then copies one byte
+/// per M-cycle. Writing again while a transfer runs restarts it, the old
+/// transfer keeps going during the new one's setup cycle.
+#[derive(Debug, Clone)]
+pub struct OamDma {
+    /// Last value written to DMA, which is what it reads back.
+    register: u8,
+    source: u16,
+    /// Next byte to copy, `OAM_SIZE` when no transfer runs.
+    index: u8,
+    /// A write's source, waiting out its setup cycle.
+    pending: Option<u16>,
+    /// Byte the transfer last put on the bus, which is what the CPU reads
+    /// outside of HRAM and the I/O registers while a transfer runs.
+    pub bus: u8,
+}
+
+impl OamDma {
+    pub fn new() -> Self {
+        Self {
+            register: 0xFF,
+            source: 0,
+            index: OAM_SIZE,
+            pending: None,
+            bus: 0xFF,
+        }
+    }
+
+    pub fn read(&self) -> u8 {
+        self.register
+    }
+
+    /// Sets what DMA reads back without starting a transfer.
+    pub fn set_register(&mut self, value: u8) {
+        self.register = value;
+    }
+
+    pub fn write(&mut self, value: u8) {
+        self.register = value;
+        self.pending = Some((value as u16) << 8);
+    }
+
+    /// Whether a transfer owns the bus.
+    pub fn active(&self) -> bool {
+        self.index < OAM_SIZE
+    }
+
+    /// Advances the transfer by an M-cycle, returns the source address and
+    /// the OAM offset of the byte to copy this cycle, if any.
+    pub fn tick(&mut self) -> Option<(u16, usize)> {
+        let transfer = self.active().then(|| {
+            let index = self.index;
+            self.index += 1;
+            (source_address(self.source + index as u16), index as usize)
+        });
+
+        if let Some(source) = self.pending.take() {
+            self.source = source;
+            self.index = 0;
+        }
+
+        transfer
+    }
+}
+
+/// The DMA unit has no view of 0xE000-0xFFFF, sources up there read the
+/// work RAM 0x2000 bytes lower, like the echo RAM does.
+fn source_address(address: u16) -> u16 {
+    if address >= 0xE000 {
+        address - 0x2000
+    } else {
+        address
+    }
+}
+
+/// Bytes HDMA copies per block, the length register counts in blocks.
+const BLOCK_SIZE: u8 = 0x10;
+
+/// CGB VRAM DMA, HDMA1-HDMA5 (0xFF51-0xFF55).
+///
+/// General purpose DMA copies everything at once and stalls the CPU until it
+/// is done, HBlank DMA copies one block at the start of every HBlank and
+/// stalls the CPU only while it does.
+#[derive(Debug, Clone)]
+pub struct Hdma {
+    source: u16,
+    /// Offset into VRAM.
+    destination: u16,
+    /// Blocks left, the transfer stops when it reaches 0.
+    blocks: u8,
+    /// Bytes left in the block being copied.
+    bytes: u8,
+    /// A transfer is running, or waiting for the next HBlank.
+    active: bool,
+    hblank: bool,
+}
+
+impl Hdma {
+    pub fn new() -> Self {
+        Self {
+            source: 0,
+            destination: 0,
+            blocks: 0,
+            bytes: 0,
+            active: false,
+            hblank: false,
+        }
+    }
+
+    /// HDMA5 reads the blocks left minus 1, bit 7 is clear while a transfer
+    /// runs. A finished transfer reads 0xFF.
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            0xFF55 => {
+                let length = self.blocks.wrapping_sub(1) & 0x7F;
+                if self.active {
+                    length
+                } else {
+                    0x80 | length
+                }
+            }
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF51 => {
+                self.source = (self.source & 0x00FF) | (value as u16) << 8
+            }
+            0xFF52 => {
+                self.source = (self.source & 0xFF00) | (value & 0xF0) as u16
+            }
+            0xFF53 => {
+                self.destination =
+                    (self.destination & 0x00FF) | ((value & 0x1F) as u16) << 8
+            }
+            0xFF54 => {
+                self.destination =
+                    (self.destination & 0xFF00) | (value & 0xF0) as u16
+            }
+            0xFF55 => {
+                // a general purpose write while HBlank DMA runs cancels it
+                if self.active && self.hblank && value & 0x80 == 0 {
+                    self.active = false;
+                    return;
+                }
+
+                self.blocks = (value & 0x7F) + 1;
+                self.active = true;
+                self.hblank = value & 0x80 != 0;
+                self.bytes = if self.hblank { 0 } else { BLOCK_SIZE };
+            }
+            _ => unreachable!("{address:#06X} is not an HDMA register"),
+        }
+    }
+
+    pub fn hblank(&self) -> bool {
+        self.active && self.hblank
+    }
+
+    /// Starts copying the next block of an HBlank DMA.
+    pub fn start_block(&mut self) {
+        if self.hblank() && self.bytes == 0 {
+            self.bytes = BLOCK_SIZE;
+        }
+    }
+
+    /// Whether the CPU is stalled by a block being copied.
+    pub fn stalling(&self) -> bool {
+        self.bytes > 0
+    }
+
+    /// Returns the source address and VRAM offset of the next byte to copy,
+    /// if a block is being copied.
+    pub fn next_byte(&mut self) -> Option<(u16, u16)> {
+        if self.bytes == 0 {
+            return None;
+        }
+
+        let transfer = (self.source, self.destination);
+        self.source = self.source.wrapping_add(1);
+        self.destination = (self.destination + 1) & 0x1FFF;
+
+        self.bytes -= 1;
+        if self.bytes == 0 {
+            self.blocks -= 1;
+            if self.blocks == 0 {
+                self.active = false;
+            } else if !self.hblank {
+                self.bytes = BLOCK_SIZE;
+            }
+        }
+
+        Some(transfer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn copy_block(hdma: &mut Hdma) -> Vec<(u16, u16)> {
+        std::iter::from_fn(|| hdma.next_byte())
+            .take(BLOCK_SIZE as usize)
+            .collect()
+    }
+
+    #[test]
+    fn general_purpose_transfer_copies_every_block_at_once() {
+        let mut hdma = Hdma::new();
+        hdma.write(0xFF51, 0xC1);
+        hdma.write(0xFF52, 0x2F);
+        hdma.write(0xFF53, 0xF8);
+        hdma.write(0xFF54, 0x1F);
+        hdma.write(0xFF55, 0x01);
+        assert_eq!(hdma.read(0xFF55), 0x01);
+
+        let transfers =
+            std::iter::from_fn(|| hdma.next_byte()).collect::<Vec<_>>();
+        assert_eq!(transfers.len(), 2 * BLOCK_SIZE as usize);
+        // the low nibbles are ignored and the destination stays in VRAM
+        assert_eq!(transfers[0], (0xC120, 0x1810));
+        assert_eq!(transfers[31], (0xC13F, 0x182F));
+
+        assert!(!hdma.stalling());
+        assert_eq!(hdma.read(0xFF55), 0xFF);
+    }
+
+    #[test]
+    fn hblank_transfer_copies_a_block_per_hblank() {
+        let mut hdma = Hdma::new();
+        hdma.write(0xFF55, 0x82);
+        assert!(hdma.hblank());
+        assert!(!hdma.stalling());
+        assert_eq!(hdma.read(0xFF55), 0x02);
+
+        hdma.start_block();
+        assert!(hdma.stalling());
+        assert_eq!(copy_block(&mut hdma).len(), BLOCK_SIZE as usize);
+        assert!(!hdma.stalling());
+        assert_eq!(hdma.next_byte(), None);
+        assert_eq!(hdma.read(0xFF55), 0x01);
+
+        hdma.start_block();
+        copy_block(&mut hdma);
+        hdma.start_block();
+        copy_block(&mut hdma);
+        assert!(!hdma.hblank());
+        assert_eq!(hdma.read(0xFF55), 0xFF);
+    }
+
+    #[test]
+    fn clearing_bit_7_cancels_an_hblank_transfer() {
+        let mut hdma = Hdma::new();
+        hdma.write(0xFF55, 0x82);
+        hdma.start_block();
+        copy_block(&mut hdma);
+
+        hdma.write(0xFF55, 0x00);
+        assert!(!hdma.hblank());
+        // bit 7 is set again and the blocks left are kept
+        assert_eq!(hdma.read(0xFF55), 0x81);
+        hdma.start_block();
+        assert!(!hdma.stalling());
+    }
+}
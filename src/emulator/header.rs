@@ -0,0 +1,388 @@
+use anyhow::{bail, Result};
+use std::fmt;
+
+/// The logo the boot ROM compares against before it lets a cartridge run.
+pub(super) const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83,
+    0x00, 0x0C, 0x00, 0x0D, 0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E,
+    0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99, 0xBB, 0xBB, 0x67, 0x63,
+    0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+/// 128 KiB, the most any mapper can bank.
+const MAX_RAM_SIZE: usize = 0x20000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CgbSupport {
+    /* 0x00 */ None,
+    /* 0x80 */ Compatible,
+    /* 0xC0 */ Only,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Licensee {
+    /// 0x014B, anything but 0x33.
+    Old(u8),
+    /// Two ASCII characters at 0x0144, used when 0x014B is 0x33.
+    New([u8; 2]),
+}
+
+impl fmt::Display for Licensee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Licensee::Old(code) => write!(f, "${code:02X}"),
+            Licensee::New(code) => {
+                write!(f, "{}", String::from_utf8_lossy(code))
+            }
+        }
+    }
+}
+
+/// The cartridge header at 0x0100-0x014F.
+#[derive(Debug, Clone)]
+pub struct Header {
+    pub title: String,
+    /// Only CGB era cartridges have one, in the last 4 bytes of the title.
+    pub manufacturer: Option<String>,
+    pub cgb: CgbSupport,
+    pub sgb: bool,
+    pub licensee: Licensee,
+    pub cartridge_type: u8,
+    pub rom_size: usize,
+    pub ram_size: usize,
+    /// Sold in Japan, or overseas.
+    pub japanese: bool,
+    pub version: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16,
+    pub logo: [u8; 48],
+    /// Where the header was found, MMM01 compilations boot into the menu in
+    /// the last 32 KiB, so that is where their header is.
+    pub offset: usize,
+}
+
+/// A header field that does not match the rest of the ROM. Real hardware
+/// only cares about the logo and the header checksum, the boot ROM locks up
+/// if either is wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderWarning {
+    Logo,
+    HeaderChecksum { expected: u8, computed: u8 },
+    GlobalChecksum { expected: u16, computed: u16 },
+    RomSize { expected: usize, actual: usize },
+    UnknownRomSize(u8),
+    UnknownRamSize(u8),
+}
+
+impl fmt::Display for HeaderWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use HeaderWarning::*;
+        match self {
+            Logo => write!(f, "the Nintendo logo does not match"),
+            HeaderChecksum { expected, computed } => write!(
+                f,
+                "header checksum is ${expected:02X} but the header sums to \
+                ${computed:02X}"
+            ),
+            GlobalChecksum { expected, computed } => write!(
+                f,
+                "global checksum is ${expected:04X} but the ROM sums to \
+                ${computed:04X}"
+            ),
+            RomSize { expected, actual } => write!(
+                f,
+                "header ROM size is {expected} bytes but the file is {actual}"
+            ),
+            UnknownRomSize(code) => write!(
+                f,
+                "unknown ROM size ${code:02X}, using the size of the file"
+            ),
+            UnknownRamSize(code) => {
+                write!(f, "unknown RAM size ${code:02X}, using 128 KiB")
+            }
+        }
+    }
+}
+
+impl Header {
+    pub fn parse(rom: &[u8]) -> Result<Header> {
+        if rom.len() < 0x0150 {
+            bail!("{} bytes is too small for a ROM with a header", rom.len());
+        }
+        let offset = header_offset(rom);
+        let header = &rom[offset..];
+
+        let cgb = match header[0x0143] {
+            0xC0 => CgbSupport::Only,
+            flag if flag & 0x80 != 0 => CgbSupport::Compatible,
+            _ => CgbSupport::None,
+        };
+
+        // CGB cartridges shortened the title to make room for the
+        // manufacturer code, but plenty of them still use all 15 bytes
+        let code = &header[0x013F..0x0143];
+        let has_manufacturer = cgb != CgbSupport::None
+            && code
+                .iter()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+        let (title, manufacturer) = if has_manufacturer {
+            (text(&header[0x0134..0x013F]), Some(text(code)))
+        } else {
+            (text(&header[0x0134..0x0143]), None)
+        };
+
+        let licensee = match header[0x014B] {
+            0x33 => Licensee::New([header[0x0144], header[0x0145]]),
+            code => Licensee::Old(code),
+        };
+
+        // unlicensed cartridges put anything here, `validate` warns about
+        // the codes that had to be guessed
+        let rom_size = rom_size(header[0x0148]).unwrap_or(rom.len());
+        let ram_size = ram_size(header[0x0149]).unwrap_or(MAX_RAM_SIZE);
+
+        Ok(Header {
+            title,
+            manufacturer,
+            cgb,
+            sgb: header[0x0146] == 0x03,
+            licensee,
+            cartridge_type: header[0x0147],
+            rom_size,
+            ram_size,
+            japanese: header[0x014A] == 0x00,
+            version: header[0x014C],
+            header_checksum: header[0x014D],
+            global_checksum: u16::from_be_bytes([
+                header[0x014E],
+                header[0x014F],
+            ]),
+            logo: header[0x0104..0x0134].try_into().unwrap(),
+            offset,
+        })
+    }
+
+    /// Checks the header against the ROM it came from.
+    pub fn validate(&self, rom: &[u8]) -> Vec<HeaderWarning> {
+        let mut warnings = Vec::new();
+        let header = &rom[self.offset..];
+
+        if self.logo != NINTENDO_LOGO {
+            warnings.push(HeaderWarning::Logo);
+        }
+
+        let computed = header_checksum(header);
+        if computed != self.header_checksum {
+            warnings.push(HeaderWarning::HeaderChecksum {
+                expected: self.header_checksum,
+                computed,
+            });
+        }
+
+        let computed = rom
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                !matches!(index.wrapping_sub(self.offset), 0x014E | 0x014F)
+            })
+            .fold(0u16, |sum, (_, byte)| sum.wrapping_add(*byte as u16));
+        if computed != self.global_checksum {
+            warnings.push(HeaderWarning::GlobalChecksum {
+                expected: self.global_checksum,
+                computed,
+            });
+        }
+
+        let code = header[0x0148];
+        if rom_size(code).is_none() {
+            warnings.push(HeaderWarning::UnknownRomSize(code));
+        }
+        let code = header[0x0149];
+        if ram_size(code).is_none() {
+            warnings.push(HeaderWarning::UnknownRamSize(code));
+        }
+
+        if rom.len() != self.rom_size {
+            warnings.push(HeaderWarning::RomSize {
+                expected: self.rom_size,
+                actual: rom.len(),
+            });
+        }
+
+        warnings
+    }
+}
+
+/// The checksum the boot ROM computes over 0x0134-0x014C.
+fn header_checksum(header: &[u8]) -> u8 {
+    header[0x0134..=0x014C]
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_sub(*byte).wrapping_sub(1))
+}
+
+/// MMM01 compilations start out showing the last 32 KiB, the header at
+/// 0x0100 belongs to whichever game was put first. The type byte alone could
+/// be game data, the logo and checksum have to check out too.
+fn header_offset(rom: &[u8]) -> usize {
+    let offset = rom.len().saturating_sub(0x8000);
+    if offset == 0 {
+        return 0;
+    }
+
+    let header = &rom[offset..];
+    let valid = matches!(header[0x0147], 0x0B..=0x0D)
+        && header[0x0104..0x0134] == NINTENDO_LOGO
+        && header_checksum(header) == header[0x014D];
+    if valid {
+        offset
+    } else {
+        0
+    }
+}
+
+fn rom_size(code: u8) -> Option<usize> {
+    match code {
+        0x00..=0x08 => Some(0x8000 << code),
+        _ => None,
+    }
+}
+
+fn ram_size(code: u8) -> Option<usize> {
+    match code {
+        0x00 => Some(0),
+        0x01 => Some(0x800),
+        0x02 => Some(0x2000),
+        0x03 => Some(0x8000),
+        0x04 => Some(MAX_RAM_SIZE),
+        0x05 => Some(0x10000),
+        _ => None,
+    }
+}
+
+/// Header text is ASCII padded with zeros.
+fn text(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|c| **c != 0)
+        .map(|c| {
+            if c.is_ascii_graphic() {
+                *c as char
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A ROM of `size` bytes with a valid header for a game called GAME.
+    fn rom(size: usize, cartridge_type: u8) -> Vec<u8> {
+        let mut rom = vec![0; size];
+        write_header(&mut rom, b"GAME", cartridge_type);
+        rom
+    }
+
+    fn write_header(header: &mut [u8], title: &[u8], cartridge_type: u8) {
+        header[0x0104..0x0134].copy_from_slice(&NINTENDO_LOGO);
+        header[0x0134..0x0134 + title.len()].copy_from_slice(title);
+        header[0x0147] = cartridge_type;
+        header[0x014D] = header_checksum(header);
+    }
+
+    #[test]
+    fn parses_header_fields() {
+        let mut rom = rom(0x10000, 0x03);
+        rom[0x0143] = 0x80;
+        rom[0x0148] = 0x01;
+        rom[0x0149] = 0x02;
+        rom[0x014B] = 0x33;
+        rom[0x0144..0x0146].copy_from_slice(b"01");
+        rom[0x014D] = header_checksum(&rom);
+
+        let header = Header::parse(&rom).unwrap();
+        assert_eq!(header.title, "GAME");
+        assert_eq!(header.cgb, CgbSupport::Compatible);
+        assert_eq!(header.cartridge_type, 0x03);
+        assert_eq!(header.rom_size, 0x10000);
+        assert_eq!(header.ram_size, 0x2000);
+        assert!(
+            matches!(header.licensee, Licensee::New(code) if &code == b"01")
+        );
+        assert_eq!(header.offset, 0);
+    }
+
+    #[test]
+    fn valid_header_has_no_warnings() {
+        let mut rom = rom(0x8000, 0x00);
+        let checksum = rom
+            .iter()
+            .fold(0u16, |sum, byte| sum.wrapping_add(*byte as u16));
+        rom[0x014E..0x0150].copy_from_slice(&checksum.to_be_bytes());
+
+        let header = Header::parse(&rom).unwrap();
+        assert_eq!(header.validate(&rom), []);
+    }
+
+    #[test]
+    fn validate_reports_bad_fields() {
+        let mut rom = rom(0x8000, 0x00);
+        rom[0x0104] ^= 0xFF;
+        rom[0x014D] ^= 0xFF;
+        rom[0x0148] = 0x01;
+
+        let header = Header::parse(&rom).unwrap();
+        let warnings = header.validate(&rom);
+        assert!(warnings.contains(&HeaderWarning::Logo));
+        assert!(warnings
+            .iter()
+            .any(|w| matches!(w, HeaderWarning::HeaderChecksum { .. })));
+        assert!(warnings
+            .iter()
+            .any(|w| matches!(w, HeaderWarning::GlobalChecksum { .. })));
+        assert!(warnings.contains(&HeaderWarning::RomSize {
+            expected: 0x10000,
+            actual: 0x8000,
+        }));
+    }
+
+    #[test]
+    fn unknown_size_codes_fall_back() {
+        let mut rom = rom(0x8000, 0x00);
+        rom[0x0148] = 0x52;
+        rom[0x0149] = 0x07;
+
+        let header = Header::parse(&rom).unwrap();
+        assert_eq!(header.rom_size, 0x8000);
+        assert_eq!(header.ram_size, MAX_RAM_SIZE);
+        let warnings = header.validate(&rom);
+        assert!(warnings.contains(&HeaderWarning::UnknownRomSize(0x52)));
+        assert!(warnings.contains(&HeaderWarning::UnknownRamSize(0x07)));
+    }
+
+    #[test]
+    fn mmm01_header_is_read_from_the_menu() {
+        let mut rom = rom(0x20000, 0x01);
+        write_header(&mut rom[0x18000..], b"MENU", 0x0B);
+
+        let header = Header::parse(&rom).unwrap();
+        assert_eq!(header.title, "MENU");
+        assert_eq!(header.cartridge_type, 0x0B);
+        assert_eq!(header.offset, 0x18000);
+    }
+
+    #[test]
+    fn mmm01_type_byte_alone_is_game_data() {
+        let mut rom = rom(0x10000, 0x01);
+        rom[0x8147] = 0x0C;
+
+        let header = Header::parse(&rom).unwrap();
+        assert_eq!(header.title, "GAME");
+        assert_eq!(header.cartridge_type, 0x01);
+        assert_eq!(header.offset, 0);
+    }
+}
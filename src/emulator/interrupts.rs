@@ -0,0 +1,79 @@
+use bitflags::bitflags;
+
+bitflags! {
+    /// Interrupt sources, in the bit order IE and IF use. Lower bits have
+    /// priority when several are pending.
+    #[derive(Debug, Clone, Copy)]
+    pub struct Interrupt: u8 {
+        const VBlank = 0b0000_0001;
+        const Stat = 0b0000_0010;
+        const Timer = 0b0000_0100;
+        const Serial = 0b0000_1000;
+        const Joypad = 0b0001_0000;
+    }
+}
+
+impl Interrupt {
+    /// Address the CPU jumps to when dispatching the interrupt.
+    pub fn vector(&self) -> u16 {
+        0x0040 + 8 * self.bits().trailing_zeros() as u16
+    }
+
+    /// Highest priority interrupt of the set.
+    pub fn highest(&self) -> Option<Interrupt> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(Interrupt::from_bits_retain(
+                1 << self.bits().trailing_zeros(),
+            ))
+        }
+    }
+}
+
+/// IE (0xFFFF) and IF (0xFF0F).
+#[derive(Debug, Clone)]
+pub struct Interrupts {
+    /// IE keeps all 8 bits even though only the low 5 select interrupts.
+    enable: u8,
+    flag: Interrupt,
+}
+
+impl Interrupts {
+    pub fn new() -> Self {
+        Self {
+            enable: 0,
+            flag: Interrupt::empty(),
+        }
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            0xFF0F => self.flag.bits() | 0b1110_0000,
+            0xFFFF => self.enable,
+            _ => unreachable!("{address:#06X} is not an interrupt register"),
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF0F => self.flag = Interrupt::from_bits_truncate(value),
+            0xFFFF => self.enable = value,
+            _ => unreachable!("{address:#06X} is not an interrupt register"),
+        }
+    }
+
+    pub fn request(&mut self, interrupt: Interrupt) {
+        self.flag |= interrupt;
+    }
+
+    pub fn acknowledge(&mut self, interrupt: Interrupt) {
+        self.flag -= interrupt;
+    }
+
+    /// Interrupts that are both requested and enabled, whether IME is set or
+    /// not.
+    pub fn pending(&self) -> Interrupt {
+        self.flag & Interrupt::from_bits_truncate(self.enable)
+    }
+}
@@ -0,0 +1,94 @@
+#[derive(Debug, Clone, Copy)]
+pub enum Button {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+impl Button {
+    /// Bit in `Joypad::pressed`, the d-pad takes the low nibble and the
+    /// buttons the high nibble, in P1 order.
+    fn mask(&self) -> u8 {
+        use Button::*;
+        match self {
+            Right => 0b0000_0001,
+            Left => 0b0000_0010,
+            Up => 0b0000_0100,
+            Down => 0b0000_1000,
+            A => 0b0001_0000,
+            B => 0b0010_0000,
+            Select => 0b0100_0000,
+            Start => 0b1000_0000,
+        }
+    }
+}
+
+/// P1 (0xFF00), the selected input lines read back low while pressed.
+#[derive(Debug, Clone)]
+pub struct Joypad {
+    /// Bits 4 and 5 of P1, a 0 selects the d-pad or the buttons.
+    select: u8,
+    pressed: u8,
+    /// Set when a selected line goes low, cleared by whoever requests the
+    /// joypad interrupt.
+    pub interrupt: bool,
+}
+
+impl Joypad {
+    pub fn new() -> Self {
+        Self {
+            select: 0b0011_0000,
+            pressed: 0,
+            interrupt: false,
+        }
+    }
+
+    pub fn read(&self) -> u8 {
+        0b1100_0000 | self.select | self.lines()
+    }
+
+    pub fn write(&mut self, value: u8) {
+        self.update(|joypad| joypad.select = value & 0b0011_0000);
+    }
+
+    pub fn press(&mut self, button: Button) {
+        self.update(|joypad| joypad.pressed |= button.mask());
+    }
+
+    pub fn release(&mut self, button: Button) {
+        self.update(|joypad| joypad.pressed &= !button.mask());
+    }
+
+    /// Whether any selected line is held low, which keeps STOP from entering
+    /// low power mode and wakes it up.
+    pub fn any_selected(&self) -> bool {
+        self.lines() != 0b1111
+    }
+
+    fn lines(&self) -> u8 {
+        let mut lines = 0b1111;
+        if self.select & 0b0001_0000 == 0 {
+            lines &= !(self.pressed & 0b1111);
+        }
+        if self.select & 0b0010_0000 == 0 {
+            lines &= !(self.pressed >> 4);
+        }
+        lines
+    }
+
+    fn update<F>(&mut self, change: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        let before = self.lines();
+        change(self);
+        if before & !self.lines() != 0 {
+            self.interrupt = true;
+        }
+    }
+}
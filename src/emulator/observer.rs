@@ -0,0 +1,105 @@
+/// What the CPU did on the bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    /// An opcode or operand fetch at the program counter.
+    Execute,
+}
+
+/// What an observer wants done with an access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Allow,
+    /// Drops a write, reads and fetches see open bus, 0xFF. Observers after
+    /// this one are not told.
+    Veto,
+    /// Writes this value instead, or has the read or fetch return it. Later
+    /// observers see the replaced value.
+    Replace(u8),
+}
+
+/// Something that watches the CPU's memory traffic, a debugger, cheat
+/// engine or logger. It is told about every access with the address, the
+/// value read or written and the M-cycle count since power on.
+pub trait BusObserver {
+    fn access(
+        &mut self,
+        access: Access,
+        address: u16,
+        value: u8,
+        cycle: u64,
+    ) -> Verdict;
+}
+
+impl<F> BusObserver for F
+where
+    F: FnMut(Access, u16, u8, u64) -> Verdict,
+{
+    fn access(
+        &mut self,
+        access: Access,
+        address: u16,
+        value: u8,
+        cycle: u64,
+    ) -> Verdict {
+        self(access, address, value, cycle)
+    }
+}
+
+/// Returned when an observer is added, removes it again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(u32);
+
+/// The registered observers, told about accesses in the order they were
+/// added.
+#[derive(Default)]
+pub struct Observers {
+    observers: Vec<(ObserverId, Box<dyn BusObserver>)>,
+    next_id: u32,
+}
+
+impl Observers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checked before anything else on every access, so an empty list costs
+    /// no more than a branch.
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    pub fn add(&mut self, observer: Box<dyn BusObserver>) -> ObserverId {
+        let id = ObserverId(self.next_id);
+        self.next_id += 1;
+        self.observers.push((id, observer));
+        id
+    }
+
+    pub fn remove(&mut self, id: ObserverId) -> Option<Box<dyn BusObserver>> {
+        let index =
+            self.observers.iter().position(|(other, _)| *other == id)?;
+        Some(self.observers.remove(index).1)
+    }
+
+    /// Runs the access past every observer, returns the value to use or None
+    /// if one of them vetoed it.
+    pub fn notify(
+        &mut self,
+        access: Access,
+        address: u16,
+        value: u8,
+        cycle: u64,
+    ) -> Option<u8> {
+        let mut value = value;
+        for (_, observer) in &mut self.observers {
+            match observer.access(access, address, value, cycle) {
+                Verdict::Allow => {}
+                Verdict::Veto => return None,
+                Verdict::Replace(replaced) => value = replaced,
+            }
+        }
+        Some(value)
+    }
+}
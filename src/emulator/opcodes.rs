@@ -0,0 +1,24 @@
+use super::{FlagFilter, FlagFilterType};
+
+/// Metadata of a single opcode, generated from ass/opcodes.json by build.rs.
+#[derive(Debug)]
+pub struct OpcodeInfo {
+    pub name: &'static str,
+    /// Size in bytes, the 0xCB prefix included.
+    pub length: u16,
+    /// T-cycles, when the branch is taken for conditional operations.
+    pub duration: u8,
+    /// T-cycles when the branch is not taken, same as `duration` for
+    /// operations that do not branch.
+    pub duration_not_taken: u8,
+    pub flags: FlagFilter,
+}
+
+include!(concat!(env!("OUT_DIR"), "/opcodes.rs"));
+
+pub fn lookup(bytecode: [u8; 4]) -> &'static OpcodeInfo {
+    match bytecode[0] {
+        0xCB => &CB_PREFIXED[bytecode[1] as usize],
+        opcode => &UNPREFIXED[opcode as usize],
+    }
+}
@@ -0,0 +1,160 @@
+use super::interrupts::Interrupt;
+
+/// Dots in a scanline, a dot is one 4 MiHz clock in either CPU speed.
+const LINE_DOTS: u16 = 456;
+const OAM_SCAN_DOTS: u16 = 80;
+const DRAWING_DOTS: u16 = 172;
+const VISIBLE_LINES: u8 = 144;
+const LINES: u8 = 154;
+
+const LCD_ENABLE: u8 = 0b1000_0000;
+const STAT_WRITABLE: u8 = 0b0111_1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /* 0 */ HBlank,
+    /* 1 */ VBlank,
+    /* 2 */ OamScan,
+    /* 3 */ Drawing,
+}
+
+impl Mode {
+    pub fn bits(&self) -> u8 {
+        match self {
+            Mode::HBlank => 0,
+            Mode::VBlank => 1,
+            Mode::OamScan => 2,
+            Mode::Drawing => 3,
+        }
+    }
+
+    /// STAT bit that selects the mode as a STAT interrupt source.
+    fn stat_select(&self) -> u8 {
+        match self {
+            Mode::HBlank => 0b0000_1000,
+            Mode::VBlank => 0b0001_0000,
+            Mode::OamScan => 0b0010_0000,
+            Mode::Drawing => 0,
+        }
+    }
+}
+
+/// LCDC, STAT, LY and LYC, and the mode timing of the scanlines. Mode 3 is
+/// taken to be a fixed length, sprites and scrolling do not stretch it.
+#[derive(Debug, Clone)]
+pub struct Ppu {
+    lcdc: u8,
+    /// The writable interrupt select bits of STAT.
+    stat: u8,
+    ly: u8,
+    lyc: u8,
+    /// Dot within the current scanline.
+    dot: u16,
+    mode: Mode,
+    /// The STAT interrupt fires on the rising edge of the OR of its sources.
+    stat_line: bool,
+    /// Interrupts raised since the last time the bus collected them.
+    pub interrupt: Interrupt,
+    /// Set on the dot mode 0 starts, cleared by whoever runs HBlank DMA.
+    pub hblank: bool,
+}
+
+impl Ppu {
+    pub fn new() -> Self {
+        Self {
+            lcdc: 0,
+            stat: 0,
+            ly: 0,
+            lyc: 0,
+            dot: 0,
+            mode: Mode::HBlank,
+            stat_line: false,
+            interrupt: Interrupt::empty(),
+            hblank: false,
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.lcdc & LCD_ENABLE != 0
+    }
+
+    pub fn tick(&mut self, dots: u32) {
+        if !self.enabled() {
+            return;
+        }
+
+        for _ in 0..dots {
+            self.dot += 1;
+            if self.dot == LINE_DOTS {
+                self.dot = 0;
+                self.ly = (self.ly + 1) % LINES;
+                if self.ly == VISIBLE_LINES {
+                    self.interrupt |= Interrupt::VBlank;
+                }
+            }
+
+            let mode = match (self.ly, self.dot) {
+                (VISIBLE_LINES.., _) => Mode::VBlank,
+                (_, ..OAM_SCAN_DOTS) => Mode::OamScan,
+                (_, dot) if dot < OAM_SCAN_DOTS + DRAWING_DOTS => Mode::Drawing,
+                _ => Mode::HBlank,
+            };
+            if mode != self.mode && mode == Mode::HBlank {
+                self.hblank = true;
+            }
+            self.mode = mode;
+
+            self.update_stat_line();
+        }
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            0xFF40 => self.lcdc,
+            0xFF41 => {
+                0b1000_0000
+                    | self.stat
+                    | ((self.ly == self.lyc) as u8) << 2
+                    | self.mode.bits()
+            }
+            0xFF44 => self.ly,
+            0xFF45 => self.lyc,
+            _ => unreachable!("{address:#06X} is not a PPU register"),
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF40 => {
+                let was_enabled = self.enabled();
+                self.lcdc = value;
+                if was_enabled && !self.enabled() {
+                    self.ly = 0;
+                    self.dot = 0;
+                    self.mode = Mode::HBlank;
+                } else if !was_enabled && self.enabled() {
+                    self.mode = Mode::OamScan;
+                }
+            }
+            0xFF41 => self.stat = value & STAT_WRITABLE,
+            0xFF44 => {}
+            0xFF45 => self.lyc = value,
+            _ => unreachable!("{address:#06X} is not a PPU register"),
+        }
+        self.update_stat_line();
+    }
+
+    fn update_stat_line(&mut self) {
+        let line = self.enabled()
+            && ((self.stat & 0b0100_0000 != 0 && self.ly == self.lyc)
+                || self.stat & self.mode.stat_select() != 0);
+        if line && !self.stat_line {
+            self.interrupt |= Interrupt::Stat;
+        }
+        self.stat_line = line;
+    }
+}
@@ -0,0 +1,112 @@
+/// DIV, TIMA, TMA and TAC, clocked once per M-cycle.
+///
+/// DIV is the upper byte of a 16 bit counter that runs at the T-cycle rate,
+/// TIMA counts the falling edges of the counter bit selected by TAC, which is
+/// why writes to DIV and TAC can bump TIMA.
+#[derive(Debug, Clone)]
+pub struct Timer {
+    counter: u16,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+    /// TIMA overflowed last M-cycle and reads as 0 until it is reloaded.
+    overflow: bool,
+    /// TIMA was reloaded from TMA this M-cycle, writes to TIMA are ignored.
+    reloaded: bool,
+    /// Set on the M-cycle TIMA is reloaded, cleared by whoever requests the
+    /// timer interrupt.
+    pub interrupt: bool,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Self {
+            counter: 0,
+            tima: 0,
+            tma: 0,
+            tac: 0,
+            overflow: false,
+            reloaded: false,
+            interrupt: false,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.reloaded = false;
+        if self.overflow {
+            self.overflow = false;
+            self.reloaded = true;
+            self.tima = self.tma;
+            self.interrupt = true;
+        }
+
+        let before = self.signal();
+        self.counter = self.counter.wrapping_add(4);
+        self.detect_edge(before);
+    }
+
+    /// Sets the internal counter without the edge a DIV write would cause.
+    pub fn set_counter(&mut self, counter: u16) {
+        self.counter = counter;
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            0xFF04 => (self.counter >> 8) as u8,
+            0xFF05 => self.tima,
+            0xFF06 => self.tma,
+            0xFF07 => self.tac | 0b1111_1000,
+            _ => unreachable!("{address:#06X} is not a timer register"),
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF04 => {
+                let before = self.signal();
+                self.counter = 0;
+                self.detect_edge(before);
+            }
+            0xFF05 => {
+                // a write during the overflow cycle cancels the reload, a
+                // write during the reload cycle loses against TMA
+                if !self.reloaded {
+                    self.tima = value;
+                    self.overflow = false;
+                }
+            }
+            0xFF06 => {
+                self.tma = value;
+                if self.reloaded {
+                    self.tima = value;
+                }
+            }
+            0xFF07 => {
+                let before = self.signal();
+                self.tac = value & 0b111;
+                self.detect_edge(before);
+            }
+            _ => unreachable!("{address:#06X} is not a timer register"),
+        }
+    }
+
+    /// Counter bit selected by TAC, ANDed with the timer enable bit.
+    fn signal(&self) -> bool {
+        let bit = match self.tac & 0b11 {
+            0b00 => 9,
+            0b01 => 3,
+            0b10 => 5,
+            _ => 7,
+        };
+
+        self.tac & 0b100 != 0 && self.counter & (1 << bit) != 0
+    }
+
+    fn detect_edge(&mut self, before: bool) {
+        if before && !self.signal() {
+            let (tima, overflow) = self.tima.overflowing_add(1);
+            self.tima = tima;
+            self.overflow = overflow;
+        }
+    }
+}
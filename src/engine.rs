@@ -1,4 +1,6 @@
 use crate::app::{self};
+use crate::options::Options;
+use crate::session::Session;
 use anyhow::{Context, Result};
 use cgmath::{Matrix4, SquareMatrix};
 use image::GenericImageView;
@@ -6,21 +8,37 @@ use pollster::FutureExt;
 use std::{path::Path, sync::Arc};
 use wgpu::{util::DeviceExt, Color};
 use winit::{
-    event::KeyEvent,
+    event::{ElementState, KeyEvent},
     keyboard::{KeyCode, PhysicalKey},
 };
 
 pub struct Engine<'a> {
     time: Time,
     renderer: Renderer<'a>,
+    session: Option<Session>,
+    /// Tilt for cartridges with an accelerometer, -1 to 1 on each axis,
+    /// from the cursor's position in the window or the IJKL keys.
+    tilt: (f32, f32),
+    /// Which of the J, L, I and K keys are held.
+    tilt_keys: [bool; 4],
+    /// Last cursor position, only a change of position moves the tilt.
+    cursor: Option<(f64, f64)>,
 }
 
 impl<'a> app::Application for Engine<'a> {
     fn new_app(window: Arc<app::Window>) -> Self {
         let renderer = Renderer::new(window).unwrap();
         let time = Time::start();
+        let session = start_session();
 
-        Self { time, renderer }
+        Self {
+            time,
+            renderer,
+            session,
+            tilt: (0.0, 0.0),
+            tilt_keys: [false; 4],
+            cursor: None,
+        }
     }
 
     fn handle_event(&mut self, event: &app::AppEvent) -> app::AppSignal {
@@ -37,7 +55,56 @@ impl<'a> app::Application for Engine<'a> {
                             ..
                         },
                     ..
-                } => AppSignal::Quit,
+                } => {
+                    if let Some(session) = &mut self.session {
+                        session.flush();
+                    }
+                    AppSignal::Quit
+                }
+
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            physical_key: PhysicalKey::Code(key),
+                            state,
+                            ..
+                        },
+                    ..
+                } => {
+                    let index = match key {
+                        KeyCode::KeyJ => 0,
+                        KeyCode::KeyL => 1,
+                        KeyCode::KeyI => 2,
+                        KeyCode::KeyK => 3,
+                        _ => return AppSignal::Continue,
+                    };
+                    self.tilt_keys[index] = *state == ElementState::Pressed;
+
+                    // opposite keys cancel out, releasing one leaves the
+                    // other in charge
+                    let [left, right, up, down] = self.tilt_keys.map(f32::from);
+                    self.tilt = (right - left, down - up);
+                    self.set_tilt();
+                    AppSignal::Continue
+                }
+
+                WindowEvent::CursorMoved { position, .. } => {
+                    let position = (position.x, position.y);
+                    if self.cursor.replace(position) == Some(position) {
+                        return AppSignal::Continue;
+                    }
+
+                    let size = self.renderer.size;
+                    let axis = |position: f64, size: u32| {
+                        (position / size.max(1) as f64 * 2.0 - 1.0) as f32
+                    };
+                    self.tilt = (
+                        axis(position.0, size.width),
+                        axis(position.1, size.height),
+                    );
+                    self.set_tilt();
+                    AppSignal::Continue
+                }
 
                 WindowEvent::Resized(new_size) => {
                     self.renderer.resize(*new_size);
@@ -52,6 +119,9 @@ impl<'a> app::Application for Engine<'a> {
 
     fn update(&mut self) -> app::AppSignal {
         self.time = self.time.next();
+        if let Some(session) = &mut self.session {
+            session.update(self.time.delta);
+        }
         self.renderer.draw();
 
         // println!("{:#?}", self.time);
@@ -59,6 +129,44 @@ impl<'a> app::Application for Engine<'a> {
         use app::AppSignal;
         AppSignal::Continue
     }
+
+    fn title(&self) -> String {
+        match &self.session {
+            Some(session) if !session.header().title.is_empty() => {
+                format!("lameboy - {}", session.header().title)
+            }
+            _ => "lameboy".to_string(),
+        }
+    }
+}
+
+impl<'a> Engine<'a> {
+    fn set_tilt(&mut self) {
+        if let Some(session) = &mut self.session {
+            session.set_tilt(self.tilt);
+        }
+    }
+}
+
+/// Loads the ROM given on the command line, if any. Errors are reported and
+/// leave the engine running without a game.
+fn start_session() -> Option<Session> {
+    let options = match Options::from_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("invalid arguments, err: {err:#}");
+            return None;
+        }
+    };
+
+    let rom = options.rom.as_ref()?;
+    match Session::new(rom, &options) {
+        Ok(session) => Some(session),
+        Err(err) => {
+            eprintln!("failed to start {rom:?}, err: {err:#}");
+            None
+        }
+    }
 }
 
 struct Renderer<'a> {
@@ -386,12 +494,12 @@ impl Pixel {
     };
 }
 
-impl Into<u32> for Pixel {
-    fn into(self) -> u32 {
-        ((self.r as u32) & 0x00_00_00_FF)
-            | (((self.g as u32) << 8) & 0x00_00_FF_00)
-            | (((self.b as u32) << 16) & 0x00_FF_00_00)
-            | (((self.a as u32) << 24) & 0xFF_00_00_00)
+impl From<Pixel> for u32 {
+    fn from(pixel: Pixel) -> u32 {
+        ((pixel.r as u32) & 0x00_00_00_FF)
+            | (((pixel.g as u32) << 8) & 0x00_00_FF_00)
+            | (((pixel.b as u32) << 16) & 0x00_FF_00_00)
+            | (((pixel.a as u32) << 24) & 0xFF_00_00_00)
     }
 }
 
@@ -451,6 +559,7 @@ fn make_texture_matrix(width: u32, height: u32, scale: f32) -> Matrix4<f32> {
     screen * Matrix4::from_nonuniform_scale(scale, scale, 1.0)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn make_render_pipeline<F>(
     device: &wgpu::Device,
     name: &str,
@@ -474,52 +583,47 @@ where
             push_constant_ranges: &[],
         });
 
-    let render_pipeline =
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(&["Render Pipeline: ", name].concat()),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                // descriptors
-                buffers: descriptors,
-                compilation_options: wgpu::PipelineCompilationOptions::default(
-                ),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(
-                ),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode,
-                unclipped_depth: false,
-                conservative: false,
-            },
-
-            // depth
-            depth_stencil: None,
-
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-            cache: None,
-        });
-
-    render_pipeline
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(&["Render Pipeline: ", name].concat()),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            // descriptors
+            buffers: descriptors,
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode,
+            unclipped_depth: false,
+            conservative: false,
+        },
+
+        // depth
+        depth_stencil: None,
+
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
 }
 
 fn load_shader<F>(device: &wgpu::Device, path: F) -> wgpu::ShaderModule
@@ -529,12 +633,10 @@ where
     let shader_source = std::fs::read(path).unwrap();
     let shader_utf8 = std::str::from_utf8(&shader_source).unwrap();
 
-    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Shader"),
         source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::from(shader_utf8)),
-    });
-
-    shader
+    })
 }
 
 fn load_texture<F>(
@@ -606,7 +708,7 @@ impl Texture {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &data,
+            data,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
                 bytes_per_row: Some(4 * width),
@@ -2,6 +2,8 @@ use engine::Engine;
 mod app;
 mod emulator;
 mod engine;
+mod options;
+mod session;
 
 pub fn run() {
     app::run::<Engine>();
@@ -0,0 +1,59 @@
+use crate::emulator::Mapper;
+use anyhow::{bail, Context, Result};
+use std::path::PathBuf;
+
+/// Command line options,
+/// `lameboy [--boot-rom <file>] [--mapper <name>] [--camera <path>]
+/// [--log-violations] [rom]`.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub rom: Option<PathBuf>,
+    /// Runs the boot ROM instead of starting in the post-boot state, its size
+    /// decides between DMG and CGB.
+    pub boot_rom: Option<PathBuf>,
+    /// Overrides the mapper detection, for unlicensed cartridges it guesses
+    /// wrong.
+    pub mapper: Option<Mapper>,
+    /// What the Pocket Camera sees, an image, or a directory of images taken
+    /// in name order as a sequence.
+    pub camera: Option<PathBuf>,
+    /// Reports VRAM and OAM accesses the PPU blocked, which only work on
+    /// inaccurate emulators.
+    pub log_violations: bool,
+}
+
+impl Options {
+    pub fn from_args() -> Result<Self> {
+        let mut options = Options::default();
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--boot-rom" => {
+                    let path =
+                        args.next().context("--boot-rom needs a file")?;
+                    options.boot_rom = Some(PathBuf::from(path));
+                }
+                "--mapper" => {
+                    let name = args.next().context("--mapper needs a name")?;
+                    options.mapper = Some(name.parse()?);
+                }
+                "--camera" => {
+                    let path = args
+                        .next()
+                        .context("--camera needs an image or a directory")?;
+                    options.camera = Some(PathBuf::from(path));
+                }
+                "--log-violations" => options.log_violations = true,
+                _ if options.rom.is_some() => {
+                    bail!(
+                        "unexpected argument {arg:?}, only one ROM can be run"
+                    )
+                }
+                _ => options.rom = Some(PathBuf::from(arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
@@ -0,0 +1,250 @@
+use crate::emulator::{
+    CgbSupport, Gameboy, Header, Mapper, Model, CAMERA_HEIGHT, CAMERA_WIDTH,
+};
+use crate::options::Options;
+use anyhow::{bail, Context, Result};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// 4 MiHz clocks in a second of emulated time.
+const CLOCK_RATE: f32 = 4.0 * 1024.0 * 1024.0;
+
+/// Longest frame the emulator catches up on, so a stall in the frontend does
+/// not turn into seconds of fast forward.
+const MAX_FRAME: f32 = 0.1;
+
+/// Blocked accesses reported per frame, a game that keeps making them would
+/// flood the terminal.
+const MAX_VIOLATIONS: usize = 16;
+
+/// How long after the game writes to its save RAM it gets flushed to disk.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A running cartridge and the `.sav` file its battery backed RAM lives in.
+pub struct Session {
+    gameboy: Gameboy,
+    header: Header,
+    /// None if the cartridge has no battery.
+    save_path: Option<PathBuf>,
+    /// When the RAM was first written since the last flush.
+    dirty_since: Option<Instant>,
+    /// The fault was already reported.
+    reported: bool,
+}
+
+impl Session {
+    pub fn new(rom_path: &Path, options: &Options) -> Result<Self> {
+        let rom = fs::read(rom_path)
+            .with_context(|| format!("failed to read ROM {rom_path:?}"))?;
+
+        let header = Header::parse(&rom)?;
+        for warning in header.validate(&rom) {
+            eprintln!("warning, {rom_path:?}: {warning}");
+        }
+
+        let boot_rom = match &options.boot_rom {
+            Some(path) => Some(fs::read(path).with_context(|| {
+                format!("failed to read boot ROM {path:?}")
+            })?),
+            None => None,
+        };
+
+        let model = match (&boot_rom, header.cgb) {
+            (Some(boot_rom), _) if boot_rom.len() == 0x100 => Model::Dmg,
+            (Some(_), _) => Model::Cgb,
+            (None, CgbSupport::None) => Model::Dmg,
+            (None, _) => Model::Cgb,
+        };
+        let mapper = match options.mapper {
+            Some(mapper) => mapper,
+            None => {
+                let mapper = Mapper::detect(&header, &rom);
+                if mapper != Mapper::Header {
+                    eprintln!(
+                        "{rom_path:?} looks like a {mapper} cartridge, \
+                         use --mapper header if it is not"
+                    );
+                }
+                mapper
+            }
+        };
+
+        let mut gameboy = Gameboy::new(model);
+        gameboy.load_rom(rom, mapper)?;
+        if let (Some(boot_rom), Some(path)) = (boot_rom, &options.boot_rom) {
+            gameboy
+                .load_boot_rom(boot_rom)
+                .with_context(|| format!("invalid boot ROM {path:?}"))?;
+        } else {
+            gameboy.skip_boot_rom();
+        }
+
+        gameboy.set_log_violations(options.log_violations);
+
+        if let Some(path) = &options.camera {
+            let frames = load_camera_frames(path)?;
+            gameboy.set_camera_frames(frames).with_context(|| {
+                format!("failed to point the camera at {path:?}")
+            })?;
+        }
+
+        let save_path = gameboy
+            .save()
+            .is_some()
+            .then(|| rom_path.with_extension("sav"));
+
+        if let Some(path) = &save_path {
+            match fs::read(path) {
+                Ok(save) => {
+                    if let Err(err) = gameboy.load_save(&save) {
+                        // the next flush would overwrite it, keep a copy in
+                        // case it was the ROM that was wrong
+                        let backup = path.with_extension("sav.bak");
+                        fs::copy(path, &backup).with_context(|| {
+                            format!("failed to back up save file {path:?}")
+                        })?;
+                        eprintln!(
+                            "warning, {path:?}: {err:#}, starting with empty \
+                             RAM, the old save was copied to {backup:?}"
+                        );
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("failed to read save file {path:?}")
+                    })
+                }
+            }
+        }
+
+        Ok(Self {
+            gameboy,
+            header,
+            save_path,
+            dirty_since: None,
+            reported: false,
+        })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn set_tilt(&mut self, (x, y): (f32, f32)) {
+        self.gameboy.set_tilt(x, y);
+    }
+
+    /// Runs the emulator for `delta` seconds of real time and flushes the save
+    /// RAM if the game has been writing to it.
+    pub fn update(&mut self, delta: f32) {
+        let clocks = delta.min(MAX_FRAME) * CLOCK_RATE;
+        self.gameboy.run(clocks as u64);
+
+        if let (Some(fault), false) = (self.gameboy.fault(), self.reported) {
+            eprintln!("emulator error, the CPU locked up: {fault}");
+            self.reported = true;
+        }
+
+        let violations = self.gameboy.take_violations();
+        for violation in violations.iter().take(MAX_VIOLATIONS) {
+            eprintln!("warning, blocked {violation}");
+        }
+        if violations.len() > MAX_VIOLATIONS {
+            eprintln!(
+                "warning, {} more blocked accesses this frame",
+                violations.len() - MAX_VIOLATIONS
+            );
+        }
+
+        if self.gameboy.take_ram_written() && self.dirty_since.is_none() {
+            self.dirty_since = Some(Instant::now());
+        }
+        if self
+            .dirty_since
+            .is_some_and(|since| since.elapsed() >= FLUSH_INTERVAL)
+        {
+            self.flush();
+        }
+    }
+
+    /// Writes the save file, if the cartridge has one.
+    pub fn flush(&mut self) {
+        let (Some(path), Some(save)) = (&self.save_path, self.gameboy.save())
+        else {
+            return;
+        };
+
+        self.dirty_since = None;
+        if let Err(err) = write_atomic(path, &save) {
+            eprintln!("failed to write save file {path:?}, err: {err:#}");
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.flush()
+    }
+}
+
+/// Loads an image, or every image in a directory in name order, as grayscale
+/// frames for the camera. Images are scaled and cropped to fill the frame.
+fn load_camera_frames(path: &Path) -> Result<Vec<Box<[u8]>>> {
+    let paths = if path.is_dir() {
+        let mut paths = fs::read_dir(path)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .with_context(|| format!("failed to list {path:?}"))?;
+        paths.retain(|path| path.is_file());
+        paths.sort();
+        paths
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    if paths.is_empty() {
+        bail!("no images in {path:?}");
+    }
+
+    paths
+        .iter()
+        .map(|path| {
+            let image = image::open(path)
+                .with_context(|| format!("failed to load image {path:?}"))?;
+            let frame = image
+                .resize_to_fill(
+                    CAMERA_WIDTH as u32,
+                    CAMERA_HEIGHT as u32,
+                    image::imageops::FilterType::Triangle,
+                )
+                .to_luma8();
+            Ok(frame.into_raw().into_boxed_slice())
+        })
+        .collect()
+}
+
+/// Writes to a temporary file next to `path` and renames it over `path`, so a
+/// crash halfway through leaves the previous save intact.
+fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    let mut file = fs::File::create(&temp_path)
+        .with_context(|| format!("failed to create {temp_path:?}"))?;
+    file.write_all(data)
+        .and_then(|_| file.sync_all())
+        .with_context(|| format!("failed to write {temp_path:?}"))?;
+    drop(file);
+
+    fs::rename(&temp_path, path)
+        .with_context(|| format!("failed to rename {temp_path:?}"))
+}